@@ -0,0 +1,332 @@
+use crate::decoration::PropKind;
+use crate::door::Door;
+use crate::dungeonmap::{DungeonMap, DungeonTile, Feature, ItemPlacement, Region};
+use crate::monster::MonsterPlacement;
+use crate::trap::{Trap, TrapKind};
+
+/// Characters used to write the map as text
+#[derive(Clone, PartialEq, Debug)]
+pub struct GlyphTable
+{
+    pub wall: char,
+    /// A floor cell of a room or a corridor
+    pub floor: char,
+    /// An empty cell outside of rooms and corridors
+    pub void: char,
+    pub closed_door: char,
+    pub open_door: char,
+    pub locked_door: char,
+    /// A secret door not discovered yet
+    pub secret_door: char,
+    pub chest: char,
+    pub key: char,
+    pub monster: char,
+    /// A trap, hidden or not
+    pub trap: char,
+    pub stairs_up: char,
+    pub stairs_down: char,
+    pub pillar: char,
+    pub table: char,
+    pub bookshelf: char,
+    pub altar: char,
+    pub rubble: char,
+    pub bones: char,
+    pub carpet: char,
+}
+
+impl Default for GlyphTable
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            wall: '#',
+            floor: '.',
+            void: ' ',
+            closed_door: '+',
+            open_door: '\'',
+            locked_door: '=',
+            secret_door: 'S',
+            chest: '$',
+            key: 'k',
+            monster: 'M',
+            trap: '^',
+            stairs_up: '<',
+            stairs_down: '>',
+            pillar: 'O',
+            table: 'T',
+            bookshelf: 'B',
+            altar: 'A',
+            rubble: ',',
+            bones: '%',
+            carpet: '~',
+        }
+    }
+}
+
+impl GlyphTable
+{
+    /// Gets the glyph of a prop
+    fn get_prop_glyph(&self, prop: PropKind) -> char
+    {
+        match prop
+        {
+            PropKind::Pillar => self.pillar,
+            PropKind::Table => self.table,
+            PropKind::Bookshelf => self.bookshelf,
+            PropKind::Altar => self.altar,
+            PropKind::Rubble => self.rubble,
+            PropKind::Bones => self.bones,
+            PropKind::Carpet => self.carpet,
+        }
+    }
+
+    /// Gets the prop drawn with the glyph
+    fn get_prop(&self, c: char) -> Option<PropKind>
+    {
+        [PropKind::Pillar, PropKind::Table, PropKind::Bookshelf, PropKind::Altar, PropKind::Rubble, PropKind::Bones, PropKind::Carpet]
+            .into_iter()
+            .find(|&p| self.get_prop_glyph(p) == c)
+    }
+
+    /// Gets a glyph for the cell at given position
+    fn get_glyph(&self, map: &DungeonMap, x: usize, y: usize) -> char
+    {
+        let tile = map.get_tile(x, y).and_then(DungeonTile::from_u8).unwrap_or(DungeonTile::TileWall);
+        let region = map.get_region(x, y).unwrap_or(Region::Void);
+
+        if map.monster_at(x, y).is_some()
+        {
+            return self.monster;
+        }
+
+        if map.get_traps().iter().any(|t| t.x == x && t.y == y)
+        {
+            return self.trap;
+        }
+
+        match tile
+        {
+            DungeonTile::TileEmpty if map.prop_at(x, y).is_some() => self.get_prop_glyph(map.prop_at(x, y).unwrap()),
+            DungeonTile::TileEmpty if region == Region::Void => self.void,
+            DungeonTile::TileEmpty => self.floor,
+            DungeonTile::TileWall if matches!(map.get_feature(x, y), Some(Feature::SecretDoor(_))) => self.secret_door,
+            DungeonTile::TileWall | DungeonTile::TileDummy => self.wall,
+            DungeonTile::TileClosedDoor if map.door_at(x, y).is_some_and(|d| d.locked) => self.locked_door,
+            DungeonTile::TileClosedDoor => self.closed_door,
+            DungeonTile::TileOpenDoor => self.open_door,
+            DungeonTile::TileChest => self.chest,
+            DungeonTile::TileKey => self.key,
+            DungeonTile::TileStairsUp => self.stairs_up,
+            DungeonTile::TileStairsDown => self.stairs_down
+        }
+    }
+}
+
+impl DungeonMap
+{
+    /// Writes the map as text, one line per map row. Void cells at the end of lines are left out,
+    /// so a map ending with void columns is read back narrower
+    /// * 'glyphs' - Characters used for the map tiles
+    pub fn to_ascii(&self, glyphs: &GlyphTable) -> String
+    {
+        let mut text = String::with_capacity((self.get_width() + 1) * self.get_height());
+
+        (0..self.get_height()).for_each(|y| {
+            let line: String = (0..self.get_width()).map(|x| glyphs.get_glyph(self, x, y)).collect();
+
+            text.push_str(line.trim_end_matches(glyphs.void));
+            text.push('\n');
+        });
+
+        text
+    }
+
+    /// Reads a map written as text. Lines shorter than the longest one are filled with the void.
+    /// Rooms and corridors can't be told apart, so all floor cells get the Region::Floor region
+    /// and doors are numbered from zero in the reading order. Monsters, chests and keys are numbered the same way,
+    /// each one gets its own id and room 0. Traps are pressure plates. A monster standing on a trap or a prop
+    /// is written with the monster glyph, so the trap or the prop is lost
+    /// * 'text' - The map text
+    /// * 'glyphs' - Characters used for the map tiles
+    pub fn from_ascii(text: &str, glyphs: &GlyphTable) -> Result<DungeonMap, String>
+    {
+        let lines: Vec<Vec<char>> = text.lines().map(|l| l.trim_end_matches('\r').chars().collect()).collect();
+        let height = lines.len();
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+
+        if width == 0
+        {
+            return Err("The map text is empty!".to_string());
+        }
+
+        let mut map = DungeonMap::new(width, height);
+        let mut door_id = 0;
+        let mut monsters: Vec<MonsterPlacement> = Vec::new();
+        let mut items: Vec<ItemPlacement> = Vec::new();
+        let mut traps: Vec<Trap> = Vec::new();
+
+        for (y, line) in lines.iter().enumerate()
+        {
+            for x in 0..width
+            {
+                let c = line.get(x).copied().unwrap_or(glyphs.void);
+
+                if c == glyphs.wall
+                {
+                    continue;
+                }
+
+                if c == glyphs.closed_door || c == glyphs.open_door || c == glyphs.locked_door || c == glyphs.secret_door
+                {
+                    map.set_door(x, y, Door { id: door_id, locked: c == glyphs.locked_door, open: c == glyphs.open_door,
+                        secret: c == glyphs.secret_door });
+                    door_id += 1;
+
+                    continue;
+                }
+
+                if c == glyphs.monster
+                {
+                    monsters.push(MonsterPlacement { monster_id: monsters.len(), room_id: 0, x, y });
+                }
+
+                if c == glyphs.trap
+                {
+                    map.set_feature(x, y, Feature::Trap(traps.len()));
+                    traps.push(Trap::new(traps.len(), TrapKind::PressurePlate, x, y));
+                }
+
+                let prop = glyphs.get_prop(c);
+
+                if let Some(p) = prop
+                {
+                    map.set_feature(x, y, Feature::Prop(p));
+                }
+
+                let tile = if c == glyphs.void || c == glyphs.floor || c == glyphs.monster || c == glyphs.trap || prop.is_some()
+                {
+                    DungeonTile::TileEmpty
+                }
+                else if c == glyphs.chest
+                {
+                    DungeonTile::TileChest
+                }
+                else if c == glyphs.key
+                {
+                    DungeonTile::TileKey
+                }
+                else if c == glyphs.stairs_up
+                {
+                    DungeonTile::TileStairsUp
+                }
+                else if c == glyphs.stairs_down
+                {
+                    DungeonTile::TileStairsDown
+                }
+                else
+                {
+                    return Err(format!("Unknown glyph '{}' at {}, {}!", c, x, y));
+                };
+
+                if tile == DungeonTile::TileChest || tile == DungeonTile::TileKey
+                {
+                    items.push(ItemPlacement { item_id: items.len(), room_id: 0, x, y });
+                }
+
+                map.set_tile(x, y, tile as u8);
+
+                if c != glyphs.void
+                {
+                    map.set_region(x, y, Region::Floor);
+                }
+            }
+        }
+
+        map.set_monster_placements(monsters);
+        map.set_item_placements(items);
+        map.set_traps(traps);
+
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::dungeon::{Dungeon, DungeonType};
+    use crate::monster::SpawnTable;
+
+    const LEVEL: &str = "\
+##########
+#.O$#..~.#
+#...+..k.#
+#.M.#..>.#
+####=###S#
+   #^#
+   ###
+";
+
+    #[test]
+    fn ascii_round_trip_test()
+    {
+        let glyphs = GlyphTable::default();
+        let map = DungeonMap::from_ascii(LEVEL, &glyphs).unwrap();
+
+        assert_eq!(map.get_width(), 10);
+        assert_eq!(map.get_height(), 7);
+        assert_eq!(map.get_tile(3, 1), Some(DungeonTile::TileChest as u8));
+        assert_eq!(map.get_tile(7, 2), Some(DungeonTile::TileKey as u8));
+        assert_eq!(map.door_at(4, 2), Some(Door { id: 0, locked: false, open: false, secret: false }));
+        assert_eq!(map.door_at(4, 4), Some(Door { id: 1, locked: true, open: false, secret: false }));
+        assert_eq!(map.get_region(1, 1), Some(Region::Floor));
+        assert_eq!(map.get_region(0, 5), Some(Region::Void));
+        assert_eq!(map.monster_at(2, 3), Some(0));
+        assert_eq!(map.get_tile(7, 3), Some(DungeonTile::TileStairsDown as u8));
+        assert_eq!(map.get_tile(2, 3), Some(DungeonTile::TileEmpty as u8));
+        assert_eq!(map.prop_at(2, 1), Some(PropKind::Pillar));
+        assert_eq!(map.prop_at(7, 1), Some(PropKind::Carpet));
+        assert_eq!(map.door_at(8, 4), Some(Door { id: 2, locked: false, open: false, secret: true }));
+        assert_eq!(map.get_tile(8, 4), Some(DungeonTile::TileWall as u8));
+        assert_eq!(map.trap_at(4, 5).map(|t| t.kind), Some(TrapKind::PressurePlate));
+        assert_eq!(map.trap_at(4, 5).map(|t| t.id), Some(0));
+        assert_eq!(map.trap_at(5, 1), None);
+        assert_eq!(map.item_at(3, 1), Some(0));
+        assert_eq!(map.item_at(7, 2), Some(1));
+        assert_eq!(map.item_at(1, 1), None);
+        assert_eq!(map.to_ascii(&glyphs), LEVEL);
+
+        assert!(DungeonMap::from_ascii("#?#", &glyphs).is_err());
+        assert!(DungeonMap::from_ascii("", &glyphs).is_err());
+    }
+
+    #[test]
+    fn generated_map_round_trip_test()
+    {
+        let mut d = Dungeon::new();
+        d.generate(6, DungeonType::Basement, 40, 40, 8, 8).unwrap();
+        d.add_doors().unwrap();
+        d.add_items(true);
+        d.add_monsters(&SpawnTable::default(), 1);
+        (0..d.get_rooms_number()).for_each(|r| d.get_room_mut(r).unwrap().trap_budget = 10);
+
+        let mut dm = DungeonMap::new(40, 40);
+        dm.create_map(&d);
+        dm.decorate(&d);
+
+        let glyphs = GlyphTable::default();
+        let text = dm.to_ascii(&glyphs);
+        let loaded = DungeonMap::from_ascii(&text, &glyphs).unwrap();
+
+        assert_eq!(loaded.to_ascii(&glyphs), text);
+
+        //Every item keeps its place and every trap not covered by a monster is read back
+        let items: Vec<(usize, usize)> = dm.get_item_placements().iter().map(|i| (i.x, i.y)).collect();
+        let loaded_items: Vec<(usize, usize)> = loaded.get_item_placements().iter().map(|i| (i.x, i.y)).collect();
+
+        assert_eq!(loaded_items.len(), items.len());
+        assert!(items.iter().all(|&(x, y)| loaded.item_at(x, y).is_some()));
+        assert!(dm.get_traps().iter().filter(|t| dm.monster_at(t.x, t.y).is_none()).all(|t| loaded.trap_at(t.x, t.y).is_some()));
+    }
+}
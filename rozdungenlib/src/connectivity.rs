@@ -0,0 +1,305 @@
+use std::collections::VecDeque;
+
+use crate::dungeon::Dungeon;
+use crate::dungeonmap::{DungeonMap, DungeonTile, Region};
+
+/// Tells which doors may be passed while checking the dungeon connectivity
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DoorPassage
+{
+    OpenOnly,   //Only open doors can be passed
+    Unlocked,   //Open and closed doors can be passed but locked ones can't
+    All,        //Every door can be passed, even the locked one
+}
+
+/// Result of the dungeon connectivity check
+pub struct ConnectivityReport
+{
+    /// Ids of rooms with no cell reachable from the start cell
+    pub unreachable_rooms: Vec<usize>,
+    /// Groups of connected floor cells which can't be reached from the start cell
+    pub isolated_pockets: Vec<Vec<(usize, usize)>>,
+    /// Positions of chests and keys which can't be reached from the start cell
+    pub stranded_items: Vec<(usize, usize)>,
+    reachable: Vec<Vec<bool>>,
+}
+
+impl ConnectivityReport
+{
+    /// Returns true if every room, floor cell and item can be reached
+    pub fn is_connected(&self) -> bool
+    {
+        self.unreachable_rooms.is_empty() && self.isolated_pockets.is_empty() && self.stranded_items.is_empty()
+    }
+
+    /// Returns true if the cell at given position was reached by the flood fill
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn is_reachable(&self, x: usize, y: usize) -> bool
+    {
+        match self.reachable.get(x)
+        {
+            Some(column) => column.get(y).copied().unwrap_or(false),
+            None => false
+        }
+    }
+}
+
+impl DungeonMap
+{
+    /// Returns true if the cell can be walked on. Doors are checked against the given passage rule,
+    /// blocking props can't be passed
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    /// * 'passage' - Which doors can be passed
+    pub fn is_passable(&self, x: usize, y: usize, passage: DoorPassage) -> bool
+    {
+        let tile = match self.get_tile(x, y)
+        {
+            Some(t) => t,
+            None => return false
+        };
+
+        if tile == DungeonTile::TileWall as u8 || tile == DungeonTile::TileDummy as u8 || self.is_blocked_by_prop(x, y)
+        {
+            return false;
+        }
+
+        let locked = match self.door_at(x, y)
+        {
+            Some(door) => door.locked,
+            None => false
+        };
+
+        if tile == DungeonTile::TileClosedDoor as u8
+        {
+            return match passage
+            {
+                DoorPassage::OpenOnly => false,
+                DoorPassage::Unlocked => !locked,
+                DoorPassage::All => true
+            };
+        }
+
+        !locked || passage == DoorPassage::All
+    }
+
+    /// Fills all cells reachable from the start cell moving in four directions
+    fn flood_fill(&self, start_x: usize, start_y: usize, passage: DoorPassage, visited: &mut [Vec<bool>]) -> Vec<(usize, usize)>
+    {
+        let mut cells: Vec<(usize, usize)> = Vec::new();
+        let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+
+        visited[start_x][start_y] = true;
+        queue.push_back((start_x, start_y));
+
+        while let Some((x, y)) = queue.pop_front()
+        {
+            cells.push((x, y));
+
+            let neighbours = [(x as isize - 1, y as isize), (x as isize + 1, y as isize),
+                (x as isize, y as isize - 1), (x as isize, y as isize + 1)];
+
+            for (nx, ny) in neighbours
+            {
+                if nx < 0 || ny < 0
+                {
+                    continue;
+                }
+
+                let (nx, ny) = (nx as usize, ny as usize);
+
+                if self.is_passable(nx, ny, passage) && !visited[nx][ny]
+                {
+                    visited[nx][ny] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        cells
+    }
+
+    /// Checks which parts of the map can be reached from the start cell.
+    /// The empty space left by filling up walls doesn't count as an isolated pocket
+    /// * 'start_x' - X coordinate of the start cell
+    /// * 'start_y' - Y coordinate of the start cell
+    /// * 'passage' - Which doors can be passed
+    pub fn validate_connectivity(&self, start_x: usize, start_y: usize, passage: DoorPassage) -> Result<ConnectivityReport, String>
+    {
+        if !self.is_passable(start_x, start_y, passage)
+        {
+            return Err("The start cell can't be walked on!".to_string());
+        }
+
+        let width = self.get_width();
+        let height = self.get_height();
+        let mut visited = vec![vec![false; height]; width];
+
+        self.flood_fill(start_x, start_y, passage, &mut visited);
+
+        let reachable = visited.clone();
+        let mut unreachable_rooms: Vec<usize> = Vec::new();
+        let mut reached_rooms: Vec<usize> = Vec::new();
+        let mut isolated_pockets: Vec<Vec<(usize, usize)>> = Vec::new();
+        let mut stranded_items: Vec<(usize, usize)> = Vec::new();
+
+        for y in 0..height
+        {
+            for x in 0..width
+            {
+                if let Some(Region::Room(id)) = self.get_region(x, y)
+                {
+                    if reachable[x][y]
+                    {
+                        reached_rooms.push(id);
+                    }
+                    else if !unreachable_rooms.contains(&id)
+                    {
+                        unreachable_rooms.push(id);
+                    }
+                }
+
+                let tile = self.get_tile(x, y).unwrap();
+
+                if !reachable[x][y] && (tile == DungeonTile::TileChest as u8 || tile == DungeonTile::TileKey as u8)
+                {
+                    stranded_items.push((x, y));
+                }
+
+                if !visited[x][y] && self.is_passable(x, y, passage)
+                {
+                    let pocket = self.flood_fill(x, y, passage, &mut visited);
+
+                    if pocket.iter().any(|&(px, py)| self.get_region(px, py) != Some(Region::Void))
+                    {
+                        isolated_pockets.push(pocket);
+                    }
+                }
+            }
+        }
+
+        unreachable_rooms.retain(|id| !reached_rooms.contains(id));
+
+        Ok(ConnectivityReport { unreachable_rooms, isolated_pockets, stranded_items, reachable })
+    }
+}
+
+impl Dungeon
+{
+    /// Gets a cell of the room the player can stand on, preferring cells not covered by other rooms.
+    /// None if the room has no such cell on the map
+    /// * 'map' - The map created from the dungeon
+    /// * 'room_id' - Room id
+    pub fn get_room_cell(&self, map: &DungeonMap, room_id: usize) -> Option<(usize, usize)>
+    {
+        let room = self.get_room_by_id(room_id)?;
+        let cells: Vec<(usize, usize)> = (room.y as usize..(room.y + room.height) as usize)
+            .flat_map(|y| (room.x as usize..(room.x + room.width) as usize).map(move |x| (x, y)))
+            .filter(|&(x, y)| map.is_passable(x, y, DoorPassage::OpenOnly) && map.door_at(x, y).is_none())
+            .collect();
+
+        cells.iter().find(|&&(x, y)| map.room_at(x, y) == Some(room_id)).or(cells.first()).copied()
+    }
+
+    /// Gets the cell the player starts on: the entrance stairs or a cell of the start room if there is no entrance
+    /// * 'map' - The map created from the dungeon
+    pub fn get_start_cell(&self, map: &DungeonMap) -> Option<(usize, usize)>
+    {
+        match self.get_entrance()
+        {
+            Some(s) if map.is_passable(s.x as usize, s.y as usize, DoorPassage::OpenOnly) => Some((s.x as usize, s.y as usize)),
+            _ => self.get_room_cell(map, self.get_start_room_id()?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::ascii::GlyphTable;
+    use crate::dungeon::DungeonType;
+
+    //Rooms 0, 1 and 2 are joined by a closed and a locked door, room 3 is walled up with a chest inside
+    const LEVEL: &str = "\
+############
+#...#..#...#
+#...+..=.k.#
+#...#..#...#
+############
+#..$#
+#####
+";
+
+    #[test]
+    fn connectivity_test()
+    {
+        let mut d = Dungeon::new();
+        d.generate(6, DungeonType::SeparateRooms, 60, 60, 8, 8).unwrap();
+        d.add_doors().unwrap();
+        d.add_items(true);
+
+        let mut dm = DungeonMap::new(60, 60);
+        dm.create_map(&d);
+
+        //Start from any room cell, chests and keys are walkable too
+        let (x, y) = (0..60 * 60).map(|i| (i % 60, i / 60)).find(|&(x, y)| dm.room_at(x, y).is_some()).unwrap();
+        let room_id = dm.room_at(x, y).unwrap();
+
+        let all = dm.validate_connectivity(x, y, DoorPassage::All).unwrap();
+        let open_only = dm.validate_connectivity(x, y, DoorPassage::OpenOnly).unwrap();
+
+        assert!(all.is_reachable(x, y));
+        assert!(!all.unreachable_rooms.contains(&room_id));
+
+        //Closing the doors can only make things worse
+        assert!(open_only.unreachable_rooms.len() >= all.unreachable_rooms.len());
+        assert!(open_only.stranded_items.len() >= all.stranded_items.len());
+
+        for &(ix, iy) in all.stranded_items.iter()
+        {
+            assert!(!all.is_reachable(ix, iy));
+        }
+
+        for pocket in all.isolated_pockets.iter()
+        {
+            assert!(pocket.iter().all(|&(px, py)| !all.is_reachable(px, py)));
+        }
+
+        assert!(dm.validate_connectivity(60, 60, DoorPassage::All).is_err());
+    }
+
+    #[test]
+    fn hand_built_map_test()
+    {
+        let mut dm = DungeonMap::from_ascii(LEVEL, &GlyphTable::default()).unwrap();
+        let rooms = [(0, 1..4, 1..4), (1, 5..7, 1..4), (2, 8..11, 1..4), (3, 1..4, 5..6)];
+
+        for (id, xs, ys) in rooms
+        {
+            xs.for_each(|x| ys.clone().for_each(|y| dm.set_region(x, y, Region::Room(id))));
+        }
+
+        let all = dm.validate_connectivity(1, 1, DoorPassage::All).unwrap();
+        let unlocked = dm.validate_connectivity(1, 1, DoorPassage::Unlocked).unwrap();
+        let open_only = dm.validate_connectivity(1, 1, DoorPassage::OpenOnly).unwrap();
+
+        assert_eq!(all.unreachable_rooms, vec![3]);
+        assert_eq!(unlocked.unreachable_rooms, vec![2, 3]);
+        assert_eq!(open_only.unreachable_rooms, vec![1, 2, 3]);
+
+        //The void filling up the short lines is not a pocket
+        assert_eq!(all.isolated_pockets.len(), 1);
+        assert_eq!(all.isolated_pockets[0].len(), 3);
+        assert!(all.isolated_pockets[0].contains(&(3, 5)));
+
+        assert_eq!(all.stranded_items, vec![(3, 5)]);
+        assert_eq!(unlocked.stranded_items, vec![(9, 2), (3, 5)]);
+        assert!(all.is_reachable(9, 2) && !unlocked.is_reachable(9, 2));
+        assert!(unlocked.is_reachable(5, 1) && !open_only.is_reachable(5, 1));
+        assert!(!all.is_connected());
+
+        assert!(dm.validate_connectivity(0, 0, DoorPassage::All).is_err());
+    }
+}
\ No newline at end of file
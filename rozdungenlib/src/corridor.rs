@@ -1,6 +1,7 @@
 use crate::door::Door;
 
 #[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Corridor
 {
     pub id: usize,
@@ -0,0 +1,350 @@
+use std::collections::VecDeque;
+
+use rand::{thread_rng, Rng};
+use rand::seq::SliceRandom;
+
+use crate::connectivity::DoorPassage;
+use crate::dungeon::Dungeon;
+use crate::dungeonmap::{DungeonMap, DungeonTile, Feature, Region};
+use crate::roles::RoomRole;
+use crate::room::Room;
+
+/// A piece of furniture or litter put in a room
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PropKind
+{
+    Pillar,
+    Table,
+    Bookshelf,
+    Altar,
+    Rubble,
+    Bones,
+    Carpet,
+}
+
+impl PropKind
+{
+    pub fn get_name(&self) -> &'static str
+    {
+        match self
+        {
+            PropKind::Pillar => "Pillar",
+            PropKind::Table => "Table",
+            PropKind::Bookshelf => "Bookshelf",
+            PropKind::Altar => "Altar",
+            PropKind::Rubble => "Rubble",
+            PropKind::Bones => "Bones",
+            PropKind::Carpet => "Carpet",
+        }
+    }
+
+    /// Returns true if the prop can't be walked through
+    pub fn is_blocking(&self) -> bool
+    {
+        matches!(self, PropKind::Pillar | PropKind::Table | PropKind::Bookshelf | PropKind::Altar)
+    }
+}
+
+impl DungeonMap
+{
+    /// Gets the prop standing at given position
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn prop_at(&self, x: usize, y: usize) -> Option<PropKind>
+    {
+        match self.get_feature(x, y)
+        {
+            Some(Feature::Prop(prop)) => Some(prop),
+            _ => None
+        }
+    }
+
+    /// Returns true if a prop stops walking through the cell
+    pub(crate) fn is_blocked_by_prop(&self, x: usize, y: usize) -> bool
+    {
+        self.prop_at(x, y).is_some_and(|p| p.is_blocking())
+    }
+
+    /// Returns true if there is a door or one of the cells next to the cell, diagonals included
+    fn is_next_to_doorway(&self, x: usize, y: usize, entries: &[(usize, usize)]) -> bool
+    {
+        (x.saturating_sub(1)..=x + 1).any(|nx| (y.saturating_sub(1)..=y + 1).any(|ny| self.door_at(nx, ny).is_some() || entries.contains(&(nx, ny))))
+    }
+
+    /// Gets cells the room can be entered from: cells outside of the room that can be walked on and touch it from a side,
+    /// like doors, corridor mouths with no doors or floor of a room next to it
+    fn get_room_entries(&self, room: &Room) -> Vec<(usize, usize)>
+    {
+        let mut entries: Vec<(usize, usize)> = Vec::new();
+
+        for (x, y) in self.get_room_cells(room)
+        {
+            for (nx, ny) in [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)]
+            {
+                let outside = !matches!(self.get_region(nx, ny), None | Some(Region::Void)) && self.get_region(nx, ny) != Some(Region::Room(room.id));
+
+                if outside && self.is_passable(nx, ny, DoorPassage::All) && !entries.contains(&(nx, ny))
+                {
+                    entries.push((nx, ny));
+                }
+            }
+        }
+
+        entries
+    }
+
+    /// Gets cells of the room that can be walked on
+    fn get_room_cells(&self, room: &Room) -> Vec<(usize, usize)>
+    {
+        (room.y as usize..(room.y + room.height) as usize)
+            .flat_map(|y| (room.x as usize..(room.x + room.width) as usize).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.get_region(x, y) == Some(Region::Room(room.id)) && self.is_passable(x, y, DoorPassage::All))
+            .collect()
+    }
+
+    /// Returns true if a wall touches the cell from a side
+    fn is_against_wall(&self, x: usize, y: usize) -> bool
+    {
+        [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)].iter()
+            .any(|&(nx, ny)| self.get_tile(nx, ny) == Some(DungeonTile::TileWall as u8))
+    }
+
+    /// Returns true if every cell of the room that can be walked on and every entry are reachable from each other
+    /// without leaving the room
+    fn is_room_connected(&self, room: &Room, entries: &[(usize, usize)]) -> bool
+    {
+        let mut cells = self.get_room_cells(room);
+        cells.extend(entries.iter().filter(|&&(x, y)| self.is_passable(x, y, DoorPassage::All)));
+
+        let start = match cells.first()
+        {
+            Some(&c) => c,
+            None => return true
+        };
+
+        let mut reached = vec![start];
+        let mut queue = VecDeque::from([start]);
+
+        while let Some((x, y)) = queue.pop_front()
+        {
+            for next in [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)]
+            {
+                if cells.contains(&next) && !reached.contains(&next)
+                {
+                    reached.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        reached.len() == cells.len()
+    }
+
+    /// Returns true if the prop can be put on the cell of the room
+    fn can_hold_prop(&self, room: &Room, entries: &[(usize, usize)], x: usize, y: usize, prop: PropKind) -> bool
+    {
+        self.get_tile(x, y) == Some(DungeonTile::TileEmpty as u8) && self.get_region(x, y) == Some(Region::Room(room.id))
+            && self.get_feature(x, y) == Some(Feature::None) && self.item_at(x, y).is_none() && self.monster_at(x, y).is_none()
+            && !(prop.is_blocking() && self.is_next_to_doorway(x, y, entries))
+    }
+
+    /// Puts the same prop on all cells or on none of them. Blocking props are taken back
+    /// if they cut off any part of the room or any of its entries. Returns true if the props were put
+    fn place_props(&mut self, room: &Room, entries: &[(usize, usize)], cells: &[(usize, usize)], prop: PropKind) -> bool
+    {
+        let mut cells = cells.to_vec();
+        cells.sort();
+        cells.dedup();
+
+        if !cells.iter().all(|&(x, y)| self.can_hold_prop(room, entries, x, y, prop))
+        {
+            return false;
+        }
+
+        cells.iter().for_each(|&(x, y)| self.set_feature(x, y, Feature::Prop(prop)));
+
+        if prop.is_blocking() && !self.is_room_connected(room, entries)
+        {
+            cells.iter().for_each(|&(x, y)| self.set_feature(x, y, Feature::None));
+
+            return false;
+        }
+
+        true
+    }
+
+    /// Furnishes the room according to its role
+    fn decorate_room(&mut self, room: &Room)
+    {
+        const LITTER_CELLS: usize = 12;
+
+        let mut rng = thread_rng();
+        let entries = self.get_room_entries(room);
+        let (x0, y0, w, h) = (room.x as usize, room.y as usize, room.width as usize, room.height as usize);
+        let (cx, cy) = (x0 + w / 2, y0 + h / 2);
+
+        //Pillars stand symmetrically in the corners of big rooms
+        if w >= 5 && h >= 5 && matches!(room.role, RoomRole::Ordinary | RoomRole::Boss | RoomRole::Hub | RoomRole::Exit)
+        {
+            self.place_props(room, &entries, &[(x0 + 1, y0 + 1), (x0 + w - 2, y0 + 1), (x0 + 1, y0 + h - 2), (x0 + w - 2, y0 + h - 2)], PropKind::Pillar);
+        }
+
+        match room.role
+        {
+            RoomRole::Boss | RoomRole::Secret =>
+            {
+                self.place_props(room, &entries, &[(cx, cy)], PropKind::Altar);
+            },
+            RoomRole::Start | RoomRole::Exit | RoomRole::Hub =>
+            {
+                (x0 + 1..(x0 + w).saturating_sub(1)).for_each(|x| { self.place_props(room, &entries, &[(x, cy)], PropKind::Carpet); });
+            },
+            RoomRole::Ordinary | RoomRole::TreasureVault if w >= 3 && h >= 3 =>
+            {
+                self.place_props(room, &entries, &[(cx, cy)], PropKind::Table);
+            },
+            _ => {}
+        }
+
+        if matches!(room.role, RoomRole::Ordinary | RoomRole::Secret)
+        {
+            let mut walls: Vec<(usize, usize)> = (y0..y0 + h)
+                .flat_map(|y| (x0..x0 + w).map(move |x| (x, y)))
+                .filter(|&(x, y)| self.is_against_wall(x, y))
+                .collect();
+
+            walls.shuffle(&mut rng);
+            walls.into_iter().take((w + h) / 4).for_each(|(x, y)| { self.place_props(room, &entries, &[(x, y)], PropKind::Bookshelf); });
+        }
+
+        if matches!(room.role, RoomRole::Ordinary | RoomRole::Boss | RoomRole::TreasureVault)
+        {
+            for _ in 0..w * h / LITTER_CELLS
+            {
+                let (x, y) = (rng.gen_range(x0..x0 + w), rng.gen_range(y0..y0 + h));
+                let prop = if rng.gen_bool(0.5) { PropKind::Rubble } else { PropKind::Bones };
+
+                self.place_props(room, &entries, &[(x, y)], prop);
+            }
+        }
+    }
+
+    /// Furnishes chambers with props chosen by room roles: symmetric pillars, tables and altars in the centre,
+    /// bookshelves against walls, carpets, rubble and bones. Blocking props never stand next to doors or corridor mouths,
+    /// no prop stands on items or monsters and props never cut off any part of a room or the way between its entries.
+    /// Must be called after create_map and again after every populate, which removes props
+    /// * 'dungeon' - The dungeon the map was created from
+    pub fn decorate(&mut self, dungeon: &Dungeon)
+    {
+        for r in 0..dungeon.get_rooms_number()
+        {
+            self.decorate_room(dungeon.get_room(r).unwrap());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::dungeon::DungeonType;
+    use crate::monster::SpawnTable;
+
+    /// Decorates a random dungeon and checks that props don't change which rooms can be reached
+    /// and which entries of every room can be reached from each other
+    fn check_decoration(with_doors: bool)
+    {
+        let mut d = Dungeon::new();
+        d.generate(10, DungeonType::Basement, 80, 80, 10, 10).unwrap();
+
+        if with_doors
+        {
+            d.add_doors().unwrap();
+        }
+
+        let mut dm = DungeonMap::new(80, 80);
+        dm.create_map(&d);
+
+        d.add_stairs(&dm, false).unwrap();
+        d.assign_room_roles().unwrap();
+        d.add_items(true);
+        d.add_monsters(&SpawnTable::default(), 1);
+        dm.populate(&d);
+
+        let (sx, sy) = d.get_start_cell(&dm).unwrap();
+        let unreachable = dm.validate_connectivity(sx, sy, DoorPassage::All).unwrap().unreachable_rooms;
+        let rooms: Vec<Room> = (0..d.get_rooms_number()).map(|r| d.get_room(r).unwrap().clone()).collect();
+        let entries: Vec<Vec<(usize, usize)>> = rooms.iter().map(|r| dm.get_room_entries(r)).collect();
+
+        let connected: Vec<bool> = rooms.iter().zip(entries.iter()).map(|(r, e)| dm.is_room_connected(r, e)).collect();
+
+        //With no doors rooms are entered only through corridor mouths and other rooms
+        assert!(with_doors || entries.iter().flatten().all(|&(x, y)| dm.door_at(x, y).is_none()));
+        assert!(entries.iter().flatten().any(|&(x, y)| dm.door_at(x, y).is_none()));
+
+        dm.decorate(&d);
+
+        let props: Vec<(usize, usize, PropKind)> = (0..80 * 80).map(|i| (i % 80, i / 80))
+            .filter_map(|(x, y)| dm.prop_at(x, y).map(|p| (x, y, p)))
+            .collect();
+
+        assert!(!props.is_empty());
+
+        for (x, y, prop) in props
+        {
+            let room = rooms.iter().position(|r| Some(r.id) == dm.room_at(x, y)).unwrap();
+
+            assert!(dm.item_at(x, y).is_none() && dm.monster_at(x, y).is_none());
+            assert_eq!(dm.is_passable(x, y, DoorPassage::All), !prop.is_blocking());
+            assert!(!prop.is_blocking() || !dm.is_next_to_doorway(x, y, &entries[room]));
+        }
+
+        assert_eq!(rooms.iter().zip(entries.iter()).map(|(r, e)| dm.is_room_connected(r, e)).collect::<Vec<bool>>(), connected);
+        assert_eq!(dm.validate_connectivity(sx, sy, DoorPassage::All).unwrap().unreachable_rooms, unreachable);
+    }
+
+    #[test]
+    fn decorate_test()
+    {
+        check_decoration(true);
+    }
+
+    #[test]
+    fn decorate_doorless_test()
+    {
+        check_decoration(false);
+    }
+
+    #[test]
+    fn decorate_again_test()
+    {
+        let mut d = Dungeon::new();
+        d.generate(10, DungeonType::Basement, 80, 80, 10, 10).unwrap();
+        d.add_doors().unwrap();
+
+        let mut dm = DungeonMap::new(80, 80);
+        dm.create_map(&d);
+        d.add_stairs(&dm, false).unwrap();
+        d.assign_room_roles().unwrap();
+        d.add_items(true);
+        d.add_monsters(&SpawnTable::default(), 1);
+
+        let cells: Vec<(usize, usize)> = (0..80 * 80).map(|i| (i % 80, i / 80)).collect();
+
+        for _ in 0..2
+        {
+            //Populating removes old props, so the content never lands under them and props are not stacked
+            dm.populate(&d);
+            assert!(cells.iter().all(|&(x, y)| dm.prop_at(x, y).is_none()));
+
+            dm.decorate(&d);
+
+            for &(x, y) in cells.iter().filter(|&&(x, y)| dm.prop_at(x, y).is_some())
+            {
+                assert_eq!(dm.get_tile(x, y), Some(DungeonTile::TileEmpty as u8));
+                assert!(dm.item_at(x, y).is_none() && dm.monster_at(x, y).is_none());
+            }
+        }
+    }
+}
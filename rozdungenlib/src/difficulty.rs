@@ -0,0 +1,225 @@
+use rand::{thread_rng, Rng};
+use rand::seq::SliceRandom;
+
+use crate::dungeon::Dungeon;
+use crate::item::{Item, ItemType};
+use crate::loot::{LootTables, DEFAULT_LOOT_TABLE};
+use crate::monster::{Monster, SpawnTable};
+
+/// Settings of the difficulty planner. Threat is measured in monster hit points
+#[derive(Clone, PartialEq, Debug)]
+pub struct DifficultySettings
+{
+    /// Level of the dungeon
+    pub level: u32,
+    /// Threat added for every corridor between the room and the entrance
+    pub threat_per_distance: u32,
+    /// Threat added to every room but the entrance for every dungeon level
+    pub threat_per_level: u32,
+    /// Part of the threat in percents spent on traps instead of monsters
+    pub trap_share: u32,
+    /// Threat worth one extra loot drop
+    pub threat_per_reward: u32,
+}
+
+impl Default for DifficultySettings
+{
+    fn default() -> Self
+    {
+        Self { level: 1, threat_per_distance: 8, threat_per_level: 4, trap_share: 25, threat_per_reward: 15 }
+    }
+}
+
+impl Monster
+{
+    /// Gets how dangerous the group is: hit points of all its members
+    pub fn get_threat(&self) -> u32
+    {
+        self.hp * self.group_size
+    }
+}
+
+impl Dungeon
+{
+    /// Gives every room a threat growing with its distance from the entrance and the dungeon level,
+    /// so the dungeon gets harder the deeper the player goes. The threat is spent on monsters,
+    /// the part meant for traps is kept in Room::trap_budget and the room gets loot matching its threat.
+    /// The threat is scaled by the room role and the loot comes from the table named after the role.
+    /// The entrance room gets no threat. Monsters and items other than keys added earlier are removed,
+    /// so planning again replaces the old spawns and loot instead of adding to them
+    /// * 'settings' - Settings of the planner
+    /// * 'spawns' - Monsters that can spawn
+    /// * 'loot' - Loot tables rewards are taken from
+    pub fn plan_difficulty(&mut self, settings: &DifficultySettings, spawns: &SpawnTable, loot: &LootTables) -> Result<(), String>
+    {
+        let start = self.get_start_room_id().ok_or("There are no rooms in the dungeon!")?;
+        loot.get_table(DEFAULT_LOOT_TABLE).ok_or("There is no default loot table!")?;
+        let distances = self.get_room_graph_distances(start);
+
+        for r in 0..self.get_rooms_number()
+        {
+            let room = self.get_room_mut(r).unwrap();
+            room.monsters.clear();
+            room.items.retain(|i| matches!(i.item_type, ItemType::Key(_)));
+        }
+
+        let max_distance = distances.iter().map(|&(_, d)| d).max().unwrap_or(0);
+        let mut monster_id = self.get_next_monster_id();
+        let mut item_id = self.get_next_item_id();
+        let mut rng = thread_rng();
+
+        for r in 0..self.get_rooms_number()
+        {
+            let room = self.get_room_mut(r).unwrap();
+
+            if room.id == start
+            {
+                room.threat = 0;
+                room.trap_budget = 0;
+                continue;
+            }
+
+            //Rooms not connected with the entrance are treated as the farthest ones
+            let distance = distances.iter().find(|&&(id, _)| id == room.id).map(|&(_, d)| d).unwrap_or(max_distance + 1) as u32;
+
+            room.threat = (distance * settings.threat_per_distance + settings.level * settings.threat_per_level) * room.role.get_threat_percent() / 100;
+            room.trap_budget = room.threat * settings.trap_share.min(100) / 100;
+
+            let mut budget = room.threat - room.trap_budget;
+
+            loop
+            {
+                let affordable: Vec<_> = spawns.entries.iter()
+                    .filter(|e| e.min_level <= settings.level && settings.level <= e.max_level)
+                    .filter(|e| e.hp_per_level * settings.level.max(1) * e.min_group.max(1) <= budget)
+                    .collect();
+
+                let entry = match affordable.choose_weighted(&mut rng, |e| e.weight)
+                {
+                    Ok(e) => *e,
+                    Err(_) => break
+                };
+
+                let hp = entry.hp_per_level * settings.level.max(1);
+                let max_group = entry.max_group.max(entry.min_group).min(budget / hp.max(1)).max(1);
+                let group = rng.gen_range(entry.min_group.clamp(1, max_group)..=max_group);
+                let monster = Monster::new(monster_id, &entry.kind, settings.level, hp, group);
+
+                budget = budget.saturating_sub(monster.get_threat());
+                room.monsters.push(monster);
+                monster_id += 1;
+
+                if hp == 0
+                {
+                    break;
+                }
+            }
+
+            //Unspent monster threat goes to traps
+            room.trap_budget += budget;
+
+            let table = loot.get_table(room.role.get_name()).unwrap();
+
+            for entry in table.roll_drops(settings.level, room.threat / settings.threat_per_reward.max(1))
+            {
+                room.items.push(Item::new_with_stats(item_id, entry.item_type, &entry.name, entry.stats.clone()));
+                item_id += 1;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::corridor::Corridor;
+    use crate::dungeon::DungeonType;
+    use crate::monster::SpawnEntry;
+    use crate::room::Room;
+
+    #[test]
+    fn plan_difficulty_test()
+    {
+        let mut d = Dungeon::new();
+        d.generate(10, DungeonType::Basement, 80, 80, 8, 8).unwrap();
+        d.assign_room_roles().unwrap();
+
+        let settings = DifficultySettings::default();
+        d.plan_difficulty(&settings, &SpawnTable::default(), &LootTables::default()).unwrap();
+
+        let start = d.get_start_room_id().unwrap();
+        let distances = d.get_room_graph_distances(start);
+
+        for (room_id, distance) in distances
+        {
+            let room = d.get_room_by_id(room_id).unwrap();
+            let monsters: u32 = room.monsters.iter().map(|m| m.get_threat()).sum();
+
+            assert_eq!(monsters + room.trap_budget, room.threat);
+
+            if room_id == start
+            {
+                assert_eq!(room.threat, 0);
+                assert!(room.monsters.is_empty());
+            }
+            else
+            {
+                let threat = distance as u32 * settings.threat_per_distance + settings.level * settings.threat_per_level;
+                assert_eq!(room.threat, threat * room.role.get_threat_percent() / 100);
+                assert_eq!(room.items.len() as u32, room.threat / settings.threat_per_reward);
+            }
+        }
+    }
+
+    #[test]
+    fn plan_difficulty_again_test()
+    {
+        //Three rooms in a row, so the room 1 is one corridor away from the entrance and the room 2 two corridors
+        let mut rooms = vec![Room::new(0, 1, 1, 4, 4), Room::new(1, 7, 1, 4, 4), Room::new(2, 13, 1, 4, 4)];
+        rooms[0].items.push(Item::new_with_stats(0, ItemType::Key(0), "Key", Default::default()));
+
+        let corridors = vec![Corridor::new(0, 0, 1, None, None), Corridor::new(1, 1, 2, None, None)];
+        let mut d = Dungeon::from_parts(rooms, corridors);
+
+        //Every monster costs exactly one threat point, so the monster budget gives the number of monsters
+        let mut spawns = SpawnTable::new(0, 0);
+        spawns.entries.push(SpawnEntry { kind: "Rat".to_string(), weight: 1, min_level: 1, max_level: 1, hp_per_level: 1, min_group: 1, max_group: 1 });
+
+        let settings = DifficultySettings { level: 1, threat_per_distance: 8, threat_per_level: 4, trap_share: 25, threat_per_reward: 6 };
+
+        //(room id, threat, trap budget, items)
+        let expected = [(0, 0, 0, 1), (1, 12, 3, 2), (2, 20, 5, 3)];
+
+        for _ in 0..2
+        {
+            d.plan_difficulty(&settings, &spawns, &LootTables::default()).unwrap();
+
+            for &(room_id, threat, trap_budget, items) in &expected
+            {
+                let room = d.get_room_by_id(room_id).unwrap();
+
+                assert_eq!(room.threat, threat);
+                assert_eq!(room.trap_budget, trap_budget);
+                assert_eq!(room.monsters.len() as u32, threat - trap_budget);
+                assert_eq!(room.items.len(), items);
+            }
+
+            //The key stays and ids are unique
+            assert!(d.get_room_by_id(0).unwrap().items.iter().any(|i| i.item_type == ItemType::Key(0)));
+
+            let mut monsters: Vec<usize> = (0..3).flat_map(|r| d.get_room(r).unwrap().monsters.iter().map(|m| m.id)).collect();
+            let mut items: Vec<usize> = (0..3).flat_map(|r| d.get_room(r).unwrap().items.iter().map(|i| i.id)).collect();
+            let (monster_count, item_count) = (monsters.len(), items.len());
+            monsters.sort_unstable();
+            monsters.dedup();
+            items.sort_unstable();
+            items.dedup();
+
+            assert_eq!(monsters.len(), monster_count);
+            assert_eq!(items.len(), item_count);
+        }
+    }
+}
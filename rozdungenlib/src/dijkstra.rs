@@ -0,0 +1,277 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::dungeonmap::{DungeonMap, DungeonTile, Region};
+use crate::pathfinding::PathSettings;
+
+/// Default factor a flee map is made with
+pub const FLEE_FACTOR: f32 = 1.2;
+
+/// A map of movement costs from the nearest source cell.
+/// Lower values are closer to the sources, cells which can't be reached have no value
+#[derive(Clone)]
+pub struct DijkstraMap
+{
+    width: usize,
+    height: usize,
+    values: Vec<Vec<Option<i32>>>,
+}
+
+impl DijkstraMap
+{
+    /// Creates a distance map from many source cells, e.g. the entrance or the player position
+    /// * 'map' - The dungeon map
+    /// * 'sources' - Cells the distances are measured from
+    /// * 'settings' - Movement costs and rules
+    pub fn new(map: &DungeonMap, sources: &[(usize, usize)], settings: &PathSettings) -> Self
+    {
+        let width = map.get_width();
+        let height = map.get_height();
+        let mut values: Vec<Vec<Option<i32>>> = vec![vec![None; height]; width];
+
+        for &(x, y) in sources
+        {
+            if x < width && y < height
+            {
+                values[x][y] = Some(0);
+            }
+        }
+
+        let mut dm = Self { width, height, values };
+        dm.scan(map, settings);
+
+        dm
+    }
+
+    /// Creates a distance map from all chests and keys on the map
+    /// * 'map' - The dungeon map
+    /// * 'settings' - Movement costs and rules
+    pub fn from_items(map: &DungeonMap, settings: &PathSettings) -> Self
+    {
+        let mut sources: Vec<(usize, usize)> = Vec::new();
+
+        (0..map.get_height()).for_each(|y| {
+            (0..map.get_width()).for_each(|x| {
+                let tile = map.get_tile(x, y).unwrap();
+
+                if tile == DungeonTile::TileChest as u8 || tile == DungeonTile::TileKey as u8
+                {
+                    sources.push((x, y));
+                }
+            });
+        });
+
+        Self::new(map, &sources, settings)
+    }
+
+    /// Propagates values from every cell having one until no value can be lowered
+    fn scan(&mut self, map: &DungeonMap, settings: &PathSettings)
+    {
+        let mut open: BinaryHeap<Reverse<(i32, usize, usize)>> = BinaryHeap::new();
+
+        (0..self.height).for_each(|y| {
+            (0..self.width).for_each(|x| {
+                if let Some(v) = self.values[x][y]
+                {
+                    open.push(Reverse((v, x, y)));
+                }
+            });
+        });
+
+        while let Some(Reverse((value, x, y))) = open.pop()
+        {
+            if Some(value) != self.values[x][y]
+            {
+                continue;
+            }
+
+            for &(dx, dy) in settings.get_directions()
+            {
+                if let Some((nx, ny, step_cost)) = map.get_move(x, y, dx, dy, settings)
+                {
+                    let new_value = value + step_cost as i32;
+
+                    if !matches!(self.values[nx][ny], Some(v) if v <= new_value)
+                    {
+                        self.values[nx][ny] = Some(new_value);
+                        open.push(Reverse((new_value, nx, ny)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Creates a map leading away from the sources. Values are negated, multiplied by the factor
+    /// and scanned again, so fleeing walkers don't get stuck in dead ends
+    /// * 'map' - The dungeon map the distance map was made for
+    /// * 'settings' - Movement costs and rules
+    /// * 'factor' - How much the walker prefers being far away over being close to an exit, usually FLEE_FACTOR
+    pub fn to_flee_map(&self, map: &DungeonMap, settings: &PathSettings, factor: f32) -> Self
+    {
+        let values = self.values.iter()
+            .map(|column| column.iter().map(|v| v.map(|v| -(v as f32 * factor).round() as i32)).collect())
+            .collect();
+
+        let mut dm = Self { width: self.width, height: self.height, values };
+        dm.scan(map, settings);
+
+        dm
+    }
+
+    /// Gets the value at given position or None if the cell is not reachable
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn get_value(&self, x: usize, y: usize) -> Option<i32>
+    {
+        if x >= self.width || y >= self.height
+        {
+            return None;
+        }
+
+        self.values[x][y]
+    }
+
+    /// Gets the neighbour cell with the lowest value, lower than the current one.
+    /// Following it leads to the nearest source (or away from it on a flee map)
+    /// * 'map' - The dungeon map the distance map was made for
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    /// * 'settings' - Movement costs and rules
+    pub fn get_downhill_step(&self, map: &DungeonMap, x: usize, y: usize, settings: &PathSettings) -> Option<(usize, usize)>
+    {
+        let mut best = (self.get_value(x, y)?, None);
+
+        for &(dx, dy) in settings.get_directions()
+        {
+            if let Some((nx, ny, _)) = map.get_move(x, y, dx, dy, settings)
+            {
+                if let Some(v) = self.get_value(nx, ny)
+                {
+                    if v < best.0
+                    {
+                        best = (v, Some((nx, ny)));
+                    }
+                }
+            }
+        }
+
+        best.1
+    }
+
+    /// Gets the lowest value found in the room or None if the room can't be reached
+    /// * 'map' - The dungeon map the distance map was made for
+    /// * 'room_id' - Room id
+    pub fn get_room_distance(&self, map: &DungeonMap, room_id: usize) -> Option<i32>
+    {
+        let mut distance: Option<i32> = None;
+
+        (0..self.height).for_each(|y| {
+            (0..self.width).for_each(|x| {
+                if map.get_region(x, y) == Some(Region::Room(room_id))
+                {
+                    if let Some(v) = self.values[x][y]
+                    {
+                        distance = Some(distance.map_or(v, |d| d.min(v)));
+                    }
+                }
+            });
+        });
+
+        distance
+    }
+
+    /// Gets distances of all reachable rooms as pairs of room id and its distance, sorted by room id
+    /// * 'map' - The dungeon map the distance map was made for
+    pub fn get_room_distances(&self, map: &DungeonMap) -> Vec<(usize, i32)>
+    {
+        let mut distances: Vec<(usize, i32)> = Vec::new();
+
+        (0..self.height).for_each(|y| {
+            (0..self.width).for_each(|x| {
+                if let (Some(Region::Room(id)), Some(v)) = (map.get_region(x, y), self.values[x][y])
+                {
+                    match distances.iter_mut().find(|d| d.0 == id)
+                    {
+                        Some(d) => d.1 = d.1.min(v),
+                        None => distances.push((id, v))
+                    }
+                }
+            });
+        });
+
+        distances.sort();
+
+        distances
+    }
+
+    /// Gets id of the reachable room lying farthest from the sources
+    /// * 'map' - The dungeon map the distance map was made for
+    pub fn get_farthest_room(&self, map: &DungeonMap) -> Option<usize>
+    {
+        self.get_room_distances(map)
+            .into_iter()
+            .max_by_key(|d| d.1)
+            .map(|d| d.0)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::ascii::GlyphTable;
+
+    #[test]
+    fn distance_map_test()
+    {
+        let mut dm = DungeonMap::new(8, 3);
+        (1..7).for_each(|x| dm.set_tile(x, 1, DungeonTile::TileEmpty as u8));
+
+        let settings = PathSettings::default();
+        let distances = DijkstraMap::new(&dm, &[(1, 1)], &settings);
+
+        assert_eq!(distances.get_value(1, 1), Some(0));
+        assert_eq!(distances.get_value(6, 1), Some(50));
+        assert_eq!(distances.get_value(0, 1), None);
+        assert_eq!(distances.get_downhill_step(&dm, 3, 1, &settings), Some((2, 1)));
+
+        //Fleeing walker goes away from the source
+        let flee = distances.to_flee_map(&dm, &settings, FLEE_FACTOR);
+        assert_eq!(flee.get_downhill_step(&dm, 3, 1, &settings), Some((4, 1)));
+    }
+
+    #[test]
+    fn room_distance_test()
+    {
+        //Rooms 0 and 1 are joined by a corridor, room 2 can't be reached
+        let mut dm = DungeonMap::from_ascii("###############\n#...###...##..#\n#.........##..#\n#...###...##..#\n###############\n",
+            &GlyphTable::default()).unwrap();
+
+        for y in 1..=3
+        {
+            (1..=3).for_each(|x| dm.set_region(x, y, Region::Room(0)));
+            (7..=9).for_each(|x| dm.set_region(x, y, Region::Room(1)));
+            (12..=13).for_each(|x| dm.set_region(x, y, Region::Room(2)));
+        }
+
+        (4..=6).for_each(|x| dm.set_region(x, 2, Region::Corridor(0)));
+
+        let distances = DijkstraMap::new(&dm, &[(1, 2)], &PathSettings::default());
+
+        assert_eq!(distances.get_room_distance(&dm, 0), Some(0));
+        assert_eq!(distances.get_room_distance(&dm, 1), Some(60));
+        assert_eq!(distances.get_room_distance(&dm, 2), None);
+        assert_eq!(distances.get_room_distances(&dm), [(0, 0), (1, 60)]);
+        assert_eq!(distances.get_farthest_room(&dm), Some(1));
+    }
+
+    #[test]
+    fn from_items_test()
+    {
+        let dm = DungeonMap::from_ascii("#######\n#$...k#\n#######\n", &GlyphTable::default()).unwrap();
+        let distances = DijkstraMap::from_items(&dm, &PathSettings::default());
+
+        let values: Vec<Option<i32>> = (0..7).map(|x| distances.get_value(x, 1)).collect();
+        assert_eq!(values, [None, Some(0), Some(10), Some(20), Some(10), Some(0), None]);
+    }
+}
@@ -1,4 +1,5 @@
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Door
 {
     //An unique id of the door
@@ -7,4 +8,6 @@ pub struct Door
     pub locked: bool,
     //True the door is opened
     pub open: bool,
+    /// If true the door looks like a wall until it is discovered
+    pub secret: bool,
 }
\ No newline at end of file
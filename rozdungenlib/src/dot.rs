@@ -0,0 +1,150 @@
+use crate::door::Door;
+use crate::dungeon::Dungeon;
+use crate::item::ItemType;
+use crate::roles::RoomRole;
+use crate::stairs::Stairs;
+
+/// Escapes text put in a quoted DOT string, so names can't break the label
+fn escape_dot(text: &str) -> String
+{
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Describes a door for the edge label
+fn get_door_label(door: &Option<Door>) -> String
+{
+    match door
+    {
+        Some(d) =>
+        {
+            let state = if d.open { "open" } else { "closed" };
+            let lock = if d.locked { ", locked" } else { "" };
+            let secret = if d.secret { ", secret" } else { "" };
+
+            format!("door {} ({}{}{})", d.id, state, lock, secret)
+        },
+        None => "no door".to_string()
+    }
+}
+
+/// Returns true if the stairs stand in the room
+fn is_stairs_room(stairs: Option<Stairs>, room_id: usize) -> bool
+{
+    stairs.is_some_and(|s| s.room_id == room_id)
+}
+
+impl Dungeon
+{
+    /// Writes the room graph in Graphviz DOT format. Rooms are nodes labelled with their id, size
+    /// and item counts, corridors are edges labelled with their doors. Corridors with a locked door are red
+    pub fn to_dot(&self) -> String
+    {
+        let mut dot = String::from("graph dungeon\n{\n    node [shape=box];\n");
+
+        for r in 0..self.get_rooms_number()
+        {
+            let room = self.get_room(r).unwrap();
+            let count = |t: ItemType| room.items.iter().filter(|i| i.item_type == t).count();
+            let keys: Vec<String> = room.items.iter()
+                .filter_map(|i| match i.item_type
+                {
+                    ItemType::Key(door_id) => Some(door_id.to_string()),
+                    _ => None
+                })
+                .collect();
+
+            let mut label = format!("Room {}\\n{}x{}\\nweapons: {}, armor: {}, potions: {}",
+                room.id, room.width, room.height, count(ItemType::Weapon), count(ItemType::Armor), count(ItemType::Potion));
+
+            let custom = room.items.iter().filter(|i| matches!(i.item_type, ItemType::Custom(_))).count();
+
+            if custom > 0
+            {
+                label.push_str(&format!("\\nother items: {}", custom));
+            }
+
+            if !room.monsters.is_empty()
+            {
+                let monsters: Vec<String> = room.monsters.iter().map(|m| format!("{}x {} ({})", m.group_size, escape_dot(&m.kind), m.level)).collect();
+                label.push_str(&format!("\\nmonsters: {}", monsters.join(", ")));
+            }
+
+            if !keys.is_empty()
+            {
+                label.push_str(&format!("\\nkeys for doors: {}", keys.join(", ")));
+            }
+
+            if room.role != RoomRole::Ordinary
+            {
+                label.push_str(&format!("\\nrole: {}", room.role.get_name()));
+            }
+
+            let mut style = "";
+
+            if is_stairs_room(self.get_entrance(), room.id)
+            {
+                label.push_str("\\nentrance");
+                style = ", peripheries=2";
+            }
+
+            if is_stairs_room(self.get_exit(), room.id)
+            {
+                label.push_str("\\nexit");
+                style = ", peripheries=2";
+            }
+
+            dot.push_str(&format!("    room{} [label=\"{}\"{}];\n", room.id, label, style));
+        }
+
+        for c in 0..self.get_corridors_number()
+        {
+            let corridor = self.get_corridor(c).unwrap();
+            let locked = [corridor.from_room_door, corridor.to_room_door].iter().flatten().any(|d| d.locked);
+            let style = if locked { ", color=red, fontcolor=red" } else { "" };
+
+            dot.push_str(&format!("    room{} -- room{} [label=\"corridor {}\\nfrom: {}\\nto: {}\"{}];\n",
+                corridor.from_room_id, corridor.to_room_id, corridor.id,
+                get_door_label(&corridor.from_room_door), get_door_label(&corridor.to_room_door), style));
+        }
+
+        dot.push_str("}\n");
+
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::corridor::Corridor;
+    use crate::item::Item;
+    use crate::monster::Monster;
+    use crate::room::Room;
+
+    #[test]
+    fn to_dot_test()
+    {
+        let door = |id, locked| Some(Door { id, locked, open: false, secret: false });
+        let mut rooms = vec![Room::new(0, 1, 1, 4, 3), Room::new(1, 10, 1, 5, 5), Room::new(2, 20, 1, 3, 3)];
+
+        rooms[0].items.push(Item::new(0, ItemType::Key(1), "Key"));
+        rooms[1].monsters.push(Monster::new(0, "Orc \"Big\" \\ Boss", 2, 10, 3));
+        rooms[2].role = RoomRole::TreasureVault;
+
+        let d = Dungeon::from_parts(rooms, vec![Corridor::new(0, 0, 1, door(0, false), None), Corridor::new(1, 1, 2, door(1, true), None)]);
+        let dot = d.to_dot();
+
+        assert!(dot.starts_with("graph dungeon\n{\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.matches(" [label=").count(), 5);
+        assert!(dot.contains("    room0 [label=\"Room 0\\n4x3\\nweapons: 0, armor: 0, potions: 0\\nkeys for doors: 1\"];\n"));
+        assert!(dot.contains("\\nmonsters: 3x Orc \\\"Big\\\" \\\\ Boss (2)\"];\n"));
+        assert!(dot.contains("\\nrole: treasure_vault\"];\n"));
+
+        assert!(dot.contains("    room0 -- room1 [label=\"corridor 0\\nfrom: door 0 (closed)\\nto: no door\"];\n"));
+        assert!(dot.contains("    room1 -- room2 [label=\"corridor 1\\nfrom: door 1 (closed, locked)\\nto: no door\", color=red, fontcolor=red];\n"));
+    }
+}
\ No newline at end of file
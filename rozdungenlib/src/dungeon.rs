@@ -1,12 +1,16 @@
 use crate::door::Door;
 use crate::item::Item;
 use crate::item::ItemType;
+use crate::light::{LightKind, LightSource};
 use crate::room::Room;
 use crate::corridor::Corridor;
+use crate::stairs::Stairs;
 use rand::thread_rng;
 use rand::Rng;
+use rand::seq::SliceRandom;
 
 #[derive(PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DungeonType
 {
     Basement,       //Like one big basement with many walls and corridors
@@ -14,19 +18,37 @@ pub enum DungeonType
     Grid,           //Rooms are aligned to the grid and connected with corridors
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Dungeon
 {
     rooms: Vec<Room>,
     corridors: Vec<Corridor>,
+    entrance: Option<Stairs>,
+    exit: Option<Stairs>,
+}
+
+impl Default for Dungeon
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
 }
 
 impl Dungeon
 {
     pub fn new() -> Self
     {
-        Self { rooms: Vec::new(), corridors: Vec::new() }
+        Self { rooms: Vec::new(), corridors: Vec::new(), entrance: None, exit: None }
     }
     
+    /// Creates a dungeon made of given rooms and corridors, so tests can work on a known layout
+    #[cfg(test)]
+    pub(crate) fn from_parts(rooms: Vec<Room>, corridors: Vec<Corridor>) -> Self
+    {
+        Self { rooms, corridors, entrance: None, exit: None }
+    }
+
     /// Gets number of rooms
     pub fn get_rooms_number(&self) -> usize
     {
@@ -39,6 +61,24 @@ impl Dungeon
         self.corridors.len()
     }
 
+    /// Gets the stairs the player enters the dungeon by
+    pub fn get_entrance(&self) -> Option<Stairs>
+    {
+        self.entrance
+    }
+
+    /// Gets the stairs the player leaves the dungeon by
+    pub fn get_exit(&self) -> Option<Stairs>
+    {
+        self.exit
+    }
+
+    pub(crate) fn set_stairs(&mut self, entrance: Option<Stairs>, exit: Option<Stairs>)
+    {
+        self.entrance = entrance;
+        self.exit = exit;
+    }
+
     /// Gets a room by its index in the array
     ///  * 'room_idx' - Room index
     pub fn get_room(&self, room_idx: usize) -> Option<&Room>
@@ -46,6 +86,13 @@ impl Dungeon
         self.rooms.get(room_idx)
     }
 
+    /// Gets a mutable room by its index in the array
+    ///  * 'room_idx' - Room index
+    pub fn get_room_mut(&mut self, room_idx: usize) -> Option<&mut Room>
+    {
+        self.rooms.get_mut(room_idx)
+    }
+
     /// Gets a room by its id
     ///  * 'room_id' - Room id
     pub fn get_room_by_id(&self, room_id: usize) -> Option<&Room>
@@ -53,6 +100,20 @@ impl Dungeon
         self.rooms.iter().find(|&x| x.id == room_id)
     }
 
+    /// Gets a mutable room by its id
+    ///  * 'room_id' - Room id
+    pub fn get_room_by_id_mut(&mut self, room_id: usize) -> Option<&mut Room>
+    {
+        self.rooms.iter_mut().find(|x| x.id == room_id)
+    }
+
+    /// Gets an item by its id
+    /// * 'item_id' - Item id
+    pub fn get_item_by_id(&self, item_id: usize) -> Option<&Item>
+    {
+        self.rooms.iter().flat_map(|r| r.items.iter()).find(|i| i.id == item_id)
+    }
+
     /// Gets a corridor by its index
     /// * 'corridor_idx' - A corridor index
     pub fn get_corridor(&self, corridor_idx: usize) -> Option<&Corridor>
@@ -60,6 +121,13 @@ impl Dungeon
         self.corridors.get(corridor_idx)
     }
 
+    /// Gets a mutable corridor by its index
+    /// * 'corridor_idx' - A corridor index
+    pub fn get_corridor_mut(&mut self, corridor_idx: usize) -> Option<&mut Corridor>
+    {
+        self.corridors.get_mut(corridor_idx)
+    }
+
     /// Counts how many doors exist in the dungeon
     pub fn get_doors_number(&self) -> usize
     {
@@ -81,6 +149,16 @@ impl Dungeon
         num
     }
 
+    /// Gets a door by its id
+    /// * 'door_id' - Door id
+    pub fn get_door_by_id(&self, door_id: usize) -> Option<Door>
+    {
+        self.corridors.iter()
+            .flat_map(|c| [c.from_room_door, c.to_room_door])
+            .flatten()
+            .find(|d| d.id == door_id)
+    }
+
     /// Gets all corridors connected to specified room
     /// * 'room' - A room to which all found corridors are connected to
     pub fn get_room_corridors(&self, room: &Room) -> Vec<&Corridor>
@@ -88,7 +166,7 @@ impl Dungeon
         let mut corridor_list:Vec<&Corridor> = Vec::new();
         let corridors: &Vec<Corridor>= &self.corridors;
 
-        corridors.into_iter().for_each(|c|{
+        corridors.iter().for_each(|c|{
             if c.from_room_id == room.id || c.to_room_id == room.id
             {
                 corridor_list.push(c);
@@ -285,30 +363,25 @@ impl Dungeon
     }
 
     /// Populate chambers with items
-    /// * 'keys' - if true the routine try to add keys for locked doors. If false no keys will be created
+    /// * 'keys' - if true keys for locked doors with no key are put in rooms reachable before the doors. If false no keys will be created
     pub fn add_items(&mut self, keys: bool)
     {
         let mut rng = thread_rng();
-        let mut item_id = 0;
-        let doors_number = self.get_doors_number();
+        let mut item_id = self.get_next_item_id();
         let rooms_number = self.get_rooms_number();
 
-        if keys && doors_number > 0
+        if rooms_number == 0
         {
-            //Add key to random room. Currently every key open every door
-            //It generates number of keys exactly equal to the number of existing doors
-            (0..doors_number).for_each(|_| {
-                let item = Item::new(item_id, ItemType::Key(0), &"Universal Key".to_string());
-                let room_idx= rng.gen_range(0..rooms_number);
-                let r = &mut self.rooms[room_idx];
-                r.items.push(item);
-
-                item_id += 1;
-            });
+            return;
+        }
+
+        if keys
+        {
+            self.add_missing_keys(&mut item_id);
         }
 
         //Gererate random items
-        let item_type_vec = vec![ItemType::Weapon, ItemType::Armor, ItemType::Potion];
+        let item_type_vec = [ItemType::Weapon, ItemType::Armor, ItemType::Potion];
         let number_items_to_generate = rng.gen_range(rooms_number..rooms_number + 2);
         let number_item_type = item_type_vec.len();
 
@@ -331,6 +404,36 @@ impl Dungeon
         }
     }
 
+    /// Puts lights in chambers. Torches are hung in corners and big rooms get a brazier in the middle
+    /// * 'dark_rooms_chance' - Chance in percents that a room stays dark
+    pub fn add_lights(&mut self, dark_rooms_chance: u8)
+    {
+        const BRAZIER_MIN_ROOM_SIZE: u16 = 5;
+
+        let mut rng = thread_rng();
+
+        self.rooms.iter_mut().for_each(|r| {
+            r.lights.clear();
+
+            if rng.gen_range(1..=100) <= dark_rooms_chance
+            {
+                return;
+            }
+
+            let corners = [(r.x, r.y), (r.x + r.width - 1, r.y), (r.x, r.y + r.height - 1), (r.x + r.width - 1, r.y + r.height - 1)];
+            let torches = rng.gen_range(1..=2);
+
+            corners.choose_multiple(&mut rng, torches).for_each(|&(x, y)| {
+                r.lights.push(LightSource::new(LightKind::Torch, x, y));
+            });
+
+            if r.width >= BRAZIER_MIN_ROOM_SIZE && r.height >= BRAZIER_MIN_ROOM_SIZE
+            {
+                r.lights.push(LightSource::new(LightKind::Brazier, r.x + r.width / 2, r.y + r.height / 2));
+            }
+        });
+    }
+
     /// Adds random doors in the dungeon. This function must be called after generate function
     pub fn add_doors(&mut self) -> Result<(), String>
     {
@@ -348,12 +451,12 @@ impl Dungeon
         self.corridors.iter_mut().for_each(|c| {
             if rng.gen_range(1..=100) <= DOOR_CREATION_CHANCE
             {
-                let d1 = Door{id: door_id, locked: false, open: rng.gen_range(0..100) > 50};
+                let d1 = Door{id: door_id, locked: false, open: rng.gen_range(0..100) > 50, secret: false};
 
                 if rng.gen_range(1..=100) <= DOORS_ON_BOTH_SIDES_CHANCE
                 {
                     door_id += 1;
-                    let d2 = Door{id: door_id, locked: false, open: false};
+                    let d2 = Door{id: door_id, locked: false, open: false, secret: false};
                     c.from_room_door = Some(d2);
                 }
 
@@ -392,8 +495,8 @@ mod tests
         assert!(count > 0);
 
         let room = &d.rooms[2];
-        let corrs = d.get_room_corridors(&room);
-        assert!(corrs.len() != 0);
+        let corrs = d.get_room_corridors(room);
+        assert!(!corrs.is_empty());
 
         d.add_doors().unwrap();
     }
@@ -409,15 +512,42 @@ mod tests
             10,
             10, 
             10);
-        match res
+        if res.is_ok()
         {
-            Ok(_) =>
-            {
-                panic!("Bad test!")
-            },
-            Err(_) =>
-            {
-            }
-        };
+            panic!("Bad test!")
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_test()
+    {
+        use crate::dungeonmap::DungeonMap;
+
+        let mut d = Dungeon::new();
+        d.generate(5, DungeonType::SeparateRooms, 40, 40, 8, 8).unwrap();
+        d.add_doors().unwrap();
+        d.add_items(true);
+        d.add_lights(20);
+
+        let mut dm = DungeonMap::new(40, 40);
+        dm.create_map(&d);
+
+        let json = serde_json::to_string(&d).unwrap();
+        let loaded: Dungeon = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+        assert_eq!(loaded.get_rooms_number(), d.get_rooms_number());
+
+        let text = ron::to_string(&d).unwrap();
+        let loaded: Dungeon = ron::from_str(&text).unwrap();
+        assert_eq!(ron::to_string(&loaded).unwrap(), text);
+
+        let json = serde_json::to_string(&dm).unwrap();
+        let loaded: DungeonMap = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+
+        let text = ron::to_string(&dm).unwrap();
+        let loaded: DungeonMap = ron::from_str(&text).unwrap();
+        assert_eq!(ron::to_string(&loaded).unwrap(), text);
     }
 }
\ No newline at end of file
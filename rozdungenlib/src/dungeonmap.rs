@@ -1,7 +1,8 @@
 use rand::{thread_rng, Rng};
 
-use crate::{dungeon::Dungeon, corridor::Corridor, door::Door, item::ItemType};
+use crate::{dungeon::Dungeon, corridor::Corridor, decoration::PropKind, door::Door, item::ItemType, monster::MonsterPlacement, trap::Trap};
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum DungeonTile
 {
     TileEmpty = 0,
@@ -11,33 +12,318 @@ pub enum DungeonTile
     TileOpenDoor,
     TileChest,
     TileKey,
+    TileStairsUp,
+    TileStairsDown,
+}
+
+impl DungeonTile
+{
+    /// Gets a tile from its value stored in the map
+    /// * 'value' - The tile value
+    pub fn from_u8(value: u8) -> Option<Self>
+    {
+        const TILES: [DungeonTile; 9] = [DungeonTile::TileEmpty, DungeonTile::TileWall, DungeonTile::TileDummy,
+            DungeonTile::TileClosedDoor, DungeonTile::TileOpenDoor, DungeonTile::TileChest, DungeonTile::TileKey,
+            DungeonTile::TileStairsUp, DungeonTile::TileStairsDown];
+
+        TILES.get(value as usize).copied()
+    }
+}
+
+/// Tells which part of the dungeon a map cell belongs to
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Region
+{
+    /// The cell is not a part of any room or corridor (walls and filled up space)
+    Void,
+    /// A floor cell not known to be a part of any room or corridor, e.g. loaded from a text map
+    Floor,
+    /// A room cell, holds the room id
+    Room(usize),
+    /// A corridor cell, holds the corridor id
+    Corridor(usize),
+    /// A doorway with the door standing in it
+    Doorway(Door),
+}
+
+/// Something put on a cell apart from its tile, kept in the feature layer of the map
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Feature
+{
+    /// Nothing special about the cell
+    None,
+    /// Stepping on the cell triggers the trap, holds the trap id
+    Trap(usize),
+    /// A secret door not discovered yet, looks like a wall. Holds the door id
+    SecretDoor(usize),
+    /// A piece of furniture or litter
+    Prop(PropKind),
+}
+
+/// Position of an item put on the map
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemPlacement
+{
+    /// Id of the item
+    pub item_id: usize,
+    /// Id of the room the item lies in
+    pub room_id: usize,
+    pub x: usize,
+    pub y: usize,
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DungeonMap
 {
     map_width: usize,
     map_height: usize,
     map: Vec<Vec<u8>>,
+    regions: Vec<Vec<Region>>,
+    items: Vec<ItemPlacement>,
+    monsters: Vec<MonsterPlacement>,
+    features: Vec<Vec<Feature>>,
+    traps: Vec<Trap>,
 }
 
 impl DungeonMap
 {
     pub fn new(width: usize, height: usize) -> Self
     {
-        let mut m:Vec<Vec<u8>> = vec![vec![0; width]; height];
-
-        (0..height).for_each(|y| {
-            (0..width).for_each(|x| {
-                m[x][y] = DungeonTile::TileWall as u8;
-            });
-        });
+        //The map is addressed as map[x][y] so columns go first
+        let m:Vec<Vec<u8>> = vec![vec![DungeonTile::TileWall as u8; height]; width];
 
         Self 
         {
             map_width: width,
             map_height: height,
             map: m,
+            regions: vec![vec![Region::Void; height]; width],
+            items: Vec::new(),
+            monsters: Vec::new(),
+            features: vec![vec![Feature::None; height]; width],
+            traps: Vec::new(),
+        }
+    }
+
+    /// Gets the map width
+    pub fn get_width(&self) -> usize
+    {
+        self.map_width
+    }
+
+    /// Gets the map height
+    pub fn get_height(&self) -> usize
+    {
+        self.map_height
+    }
+
+    /// Gets a tile at given position or None if the position is outside of the map
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn get_tile(&self, x: usize, y: usize) -> Option<u8>
+    {
+        self.get_field(x as isize, y as isize)
+    }
+
+    /// Gets a region the cell at given position belongs to
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn get_region(&self, x: usize, y: usize) -> Option<Region>
+    {
+        if x >= self.map_width || y >= self.map_height
+        {
+            return None;
+        }
+
+        Some(self.regions[x][y])
+    }
+
+    /// Gets id of the room the cell at given position belongs to
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn room_at(&self, x: usize, y: usize) -> Option<usize>
+    {
+        match self.get_region(x, y)
+        {
+            Some(Region::Room(id)) => Some(id),
+            _ => None
+        }
+    }
+
+    /// Gets id of the corridor the cell at given position belongs to
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn corridor_at(&self, x: usize, y: usize) -> Option<usize>
+    {
+        match self.get_region(x, y)
+        {
+            Some(Region::Corridor(id)) => Some(id),
+            _ => None
+        }
+    }
+
+    /// Gets a door standing at given position
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn door_at(&self, x: usize, y: usize) -> Option<Door>
+    {
+        match self.get_region(x, y)
+        {
+            Some(Region::Doorway(door)) => Some(door),
+            _ => None
+        }
+    }
+
+    /// Gets positions of all items put on the map
+    pub fn get_item_placements(&self) -> &[ItemPlacement]
+    {
+        &self.items
+    }
+
+    /// Gets id of the item lying at given position
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn item_at(&self, x: usize, y: usize) -> Option<usize>
+    {
+        self.items.iter().find(|i| i.x == x && i.y == y).map(|i| i.item_id)
+    }
+
+    /// Gets positions of all monsters put on the map, one for every member of a group
+    pub fn get_monster_placements(&self) -> &[MonsterPlacement]
+    {
+        &self.monsters
+    }
+
+    /// Gets id of the monster standing at given position
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn monster_at(&self, x: usize, y: usize) -> Option<usize>
+    {
+        self.monsters.iter().find(|m| m.x == x && m.y == y).map(|m| m.monster_id)
+    }
+
+    /// Gets a feature of the cell at given position
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn get_feature(&self, x: usize, y: usize) -> Option<Feature>
+    {
+        if x >= self.map_width || y >= self.map_height
+        {
+            return None;
+        }
+
+        Some(self.features[x][y])
+    }
+
+    /// Gets all traps put on the map
+    pub fn get_traps(&self) -> &[Trap]
+    {
+        &self.traps
+    }
+
+    /// Gets the trap triggered by stepping on the cell at given position
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn trap_at(&self, x: usize, y: usize) -> Option<&Trap>
+    {
+        match self.get_feature(x, y)
+        {
+            Some(Feature::Trap(id)) => self.traps.iter().find(|t| t.id == id),
+            _ => None
+        }
+    }
+
+    /// Reveals a hidden trap, e.g. when the player searches around or triggers it.
+    /// Returns false if there is no such trap
+    /// * 'trap_id' - Id of the trap
+    pub fn reveal_trap(&mut self, trap_id: usize) -> bool
+    {
+        match self.traps.iter_mut().find(|t| t.id == trap_id)
+        {
+            Some(trap) =>
+            {
+                trap.hidden = false;
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Sets a tile at given position. Positions outside of the map are ignored
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    /// * 'tile' - A new tile
+    pub fn set_tile(&mut self, x: usize, y: usize, tile: u8)
+    {
+        if x < self.map_width && y < self.map_height
+        {
+            self.map[x][y] = tile;
+        }
+    }
+
+    /// Sets a region of the cell at given position. Positions outside of the map are ignored
+    pub(crate) fn set_region(&mut self, x: usize, y: usize, region: Region)
+    {
+        if x < self.map_width && y < self.map_height
+        {
+            self.regions[x][y] = region;
+        }
+    }
+
+    /// Sets a feature of the cell at given position. Positions outside of the map are ignored
+    pub(crate) fn set_feature(&mut self, x: usize, y: usize, feature: Feature)
+    {
+        if x < self.map_width && y < self.map_height
+        {
+            self.features[x][y] = feature;
+        }
+    }
+
+    /// Replaces all traps put on the map. Feature cells of the traps are not changed
+    pub(crate) fn set_traps(&mut self, traps: Vec<Trap>)
+    {
+        self.traps = traps;
+    }
+
+    /// Replaces positions of all items put on the map
+    pub(crate) fn set_item_placements(&mut self, items: Vec<ItemPlacement>)
+    {
+        self.items = items;
+    }
+
+    /// Replaces positions of all monsters put on the map
+    pub(crate) fn set_monster_placements(&mut self, monsters: Vec<MonsterPlacement>)
+    {
+        self.monsters = monsters;
+    }
+
+    /// Puts a door at given position, e.g. to open or unlock it during the game.
+    /// A secret door is put as a wall tile with the Feature::SecretDoor feature. Positions outside of the map are ignored
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    /// * 'door' - The door
+    pub fn set_door(&mut self, x: usize, y: usize, door: Door)
+    {
+        if x < self.map_width && y < self.map_height
+        {
+            let tile = match (door.secret, door.open)
+            {
+                (true, _) => DungeonTile::TileWall,
+                (false, true) => DungeonTile::TileOpenDoor,
+                (false, false) => DungeonTile::TileClosedDoor
+            };
+
+            self.map[x][y] = tile as u8;
+            self.regions[x][y] = Region::Doorway(door);
+            self.features[x][y] = match door.secret
+            {
+                true => Feature::SecretDoor(door.id),
+                false => Feature::None
+            };
         }
     }
 
@@ -61,6 +347,7 @@ impl DungeonMap
                         let xs = x + room.x as usize;
 
                         self.map[xs][ys] = DungeonTile::TileEmpty as u8;
+                        self.regions[xs][ys] = Region::Room(room.id);
                     }    
                 }
             }
@@ -72,16 +359,14 @@ impl DungeonMap
         let from_room = dungeon.get_room_by_id(corridor.from_room_id).unwrap();
         let to_room = dungeon.get_room_by_id(corridor.to_room_id).unwrap();
 
-        let left_room = if from_room.x + from_room.width <= to_room.x
+        if from_room.x + from_room.width <= to_room.x
         {
             (from_room.x + from_room.width, from_room.y, from_room.height)
         }
         else
         {
             (to_room.x, to_room.y, to_room.height)
-        };
-
-        left_room
+        }
     }
 
     fn get_right_wall(&self, corridor: &Corridor, dungeon: &Dungeon) -> (u16, u16, u16)
@@ -89,16 +374,14 @@ impl DungeonMap
         let from_room = dungeon.get_room_by_id(corridor.from_room_id).unwrap();
         let to_room = dungeon.get_room_by_id(corridor.to_room_id).unwrap();
 
-        let right_room = if from_room.x + from_room.width > to_room.x
+        if from_room.x + from_room.width > to_room.x
         {
             (from_room.x + from_room.width, from_room.y, from_room.height)
         }
         else
         {
             (to_room.x, to_room.y, to_room.height)
-        };
-
-        right_room
+        }
     }
 
     fn get_door_from(&self, corridor: &Corridor, dungeon: &Dungeon) -> Option<Door>
@@ -121,57 +404,72 @@ impl DungeonMap
         }
     }
 
-    fn create_door_from(&mut self, corridor: &Corridor, dungeon: &Dungeon, prev_x: usize, room_wall_y: usize)
+    fn create_door_from(&mut self, corridor: &Corridor, dungeon: &Dungeon, prev_x: usize, room_wall_y: usize) -> Option<Door>
     {
-        match self.get_door_from(corridor, dungeon)
+        self.carve_corridor(corridor, prev_x, room_wall_y);
+
+        let door = self.get_door_from(corridor, dungeon);
+
+        if let Some(x) = door
         {
-            Some(x) => 
+            let tile = match x.open
             {
-                let tile = match x.open
-                {
-                    true => DungeonTile::TileOpenDoor,
-                    false => DungeonTile::TileClosedDoor
-                };
-
-                self.map[prev_x][room_wall_y] = tile as u8;
-            },
+                true => DungeonTile::TileOpenDoor,
+                false => DungeonTile::TileClosedDoor
+            };
 
-            None => self.map[prev_x][room_wall_y] = DungeonTile::TileEmpty as u8
+            self.map[prev_x][room_wall_y] = tile as u8;
         }
+
+        door
     }
 
-    fn create_door_to(&mut self, corridor: &Corridor, dungeon: &Dungeon, prev_x: usize, room_wall_y: usize)
+    fn create_door_to(&mut self, corridor: &Corridor, dungeon: &Dungeon, prev_x: usize, room_wall_y: usize) -> Option<Door>
     {
-        match self.get_door_to(corridor, dungeon)
+        self.carve_corridor(corridor, prev_x, room_wall_y);
+
+        let door = self.get_door_to(corridor, dungeon);
+
+        if let Some(x) = door
         {
-            Some(x) => 
+            let tile = match x.open
             {
-                let tile = match x.open
-                {
-                    true => DungeonTile::TileOpenDoor,
-                    false => DungeonTile::TileClosedDoor
-                };
+                true => DungeonTile::TileOpenDoor,
+                false => DungeonTile::TileClosedDoor
+            };
 
-                self.map[prev_x][room_wall_y] = tile as u8;
-            },
+            self.map[prev_x][room_wall_y] = tile as u8;
+        }
 
-            None => self.map[prev_x][room_wall_y] = DungeonTile::TileEmpty as u8
+        door
+    }
+
+    /// Makes an empty corridor cell. Cells already belonging to a room or another corridor keep their region
+    fn carve_corridor(&mut self, corridor: &Corridor, x: usize, y: usize)
+    {
+        self.map[x][y] = DungeonTile::TileEmpty as u8;
+
+        if self.regions[x][y] == Region::Void
+        {
+            self.regions[x][y] = Region::Corridor(corridor.id);
         }
     }
 
-    fn create_corridors(&mut self, dungeon: &Dungeon)
+    /// Creates corridors and returns positions of all doors put on the map
+    fn create_corridors(&mut self, dungeon: &Dungeon) -> Vec<(usize, usize, Door)>
     {
         const MIN_CORRIDOR_LENGTH_FOR_DOOR: u16 = 3;
 
         let mut rng = thread_rng();
         let corridors_number = dungeon.get_corridors_number();
+        let mut doors: Vec<(usize, usize, Door)> = Vec::new();
 
         for c in 0..corridors_number
         {
             if let Some(corridor) = dungeon.get_corridor(c)
             {
-                let left_room = self.get_left_wall(&corridor, dungeon);
-                let right_room = self.get_right_wall(&corridor, dungeon);
+                let left_room = self.get_left_wall(corridor, dungeon);
+                let right_room = self.get_right_wall(corridor, dungeon);
 
                 //Find random right place in the wall of the left room to start drawing a corridor from
                 let left_room_wall_y = rng.gen_range(0..left_room.2) + left_room.1;
@@ -190,12 +488,15 @@ impl DungeonMap
                     if x == 1 && corridor_x_len >= MIN_CORRIDOR_LENGTH_FOR_DOOR
                     {
                         prev_x = (pos_x0 + x) as usize;                    
-                        self.create_door_from(corridor, dungeon, prev_x, left_room_wall_y as usize);
+                        if let Some(door) = self.create_door_from(corridor, dungeon, prev_x, left_room_wall_y as usize)
+                        {
+                            doors.push((prev_x, left_room_wall_y as usize, door));
+                        }
                     }
                     else
                     {
                         prev_x = (pos_x0 + x) as usize;                    
-                        self.map[prev_x][left_room_wall_y as usize] = DungeonTile::TileEmpty as u8;    
+                        self.carve_corridor(corridor, prev_x, left_room_wall_y as usize);
                     }
                 }
 
@@ -211,16 +512,23 @@ impl DungeonMap
 
                 for y in 0..=corridor_y_len
                 {
-                    self.map[prev_x][(left_room_wall_y as isize + y as isize * incr) as usize] = DungeonTile::TileEmpty as u8
+                    self.carve_corridor(corridor, prev_x, (left_room_wall_y as isize + y as isize * incr) as usize);
                 }
 
                 //Create door 2 if it does exist and the length is right
                 if corridor_y_len >= MIN_CORRIDOR_LENGTH_FOR_DOOR
                 {
-                    self.create_door_to(corridor, dungeon, prev_x, (left_room_wall_y as isize + (corridor_y_len - 2) as isize * incr) as usize);
+                    let door_y = (left_room_wall_y as isize + (corridor_y_len - 2) as isize * incr) as usize;
+
+                    if let Some(door) = self.create_door_to(corridor, dungeon, prev_x, door_y)
+                    {
+                        doors.push((prev_x, door_y, door));
+                    }
                 }
             }
         }
+
+        doors
     }
 
     fn create_items(&mut self, dungeon: &Dungeon)
@@ -242,10 +550,9 @@ impl DungeonMap
                     let r_y = rng.gen_range(room.y..room.y + room.height) as usize;
     
                     let tile = self.map[r_x][r_y];
-                    if tile != DungeonTile::TileChest as u8 && tile != DungeonTile::TileKey as u8
+                    if tile == DungeonTile::TileEmpty as u8
                     {
-                        //Currently we won't store any item information
-                        if i.item_type == ItemType::Key(0)
+                        if matches!(i.item_type, ItemType::Key(_))
                         {
                             self.map[r_x][r_y] = DungeonTile::TileKey as u8;    
                         }
@@ -254,6 +561,8 @@ impl DungeonMap
                             self.map[r_x][r_y] = DungeonTile::TileChest as u8;    
                         }
 
+                        self.items.push(ItemPlacement { item_id: i.id, room_id: room.id, x: r_x, y: r_y });
+
                         break;
                     }
 
@@ -282,11 +591,11 @@ impl DungeonMap
         {
             Some(x) =>
             {
-                return x == DungeonTile::TileWall as u8 || x == DungeonTile::TileDummy as u8;
+                x == DungeonTile::TileWall as u8 || x == DungeonTile::TileDummy as u8
             },
             None =>
             {
-                return true;
+                true
             }
         }
     }
@@ -308,7 +617,7 @@ impl DungeonMap
     {
         (0..self.map_height as isize).for_each(|y| {
             (0..self.map_width as isize).for_each(|x| {
-                if self.has_walls_around(x, y) == true
+                if self.has_walls_around(x, y)
                 {
                     self.map[x as usize][y as usize] = DungeonTile::TileDummy as u8;
                 }
@@ -345,25 +654,156 @@ impl DungeonMap
             (0..self.map_width).for_each(|x| {
                 let tile = self.map[x][y];
 
-                if tile == DungeonTile::TileClosedDoor as u8
+                if tile == DungeonTile::TileClosedDoor as u8 && !self.is_valid_door_position(x as isize, y as isize)
                 {
-                    if !self.is_valid_door_position(x as isize, y as isize)
-                    {
-                        self.map[x][y] = DungeonTile::TileEmpty as u8;
-                    }
+                    self.map[x][y] = DungeonTile::TileEmpty as u8;
                 }
             });
         });
     }
 
+    /// Marks cells still holding a door as doorways. Must be called after not useful doors are removed
+    /// * 'doors' - Positions of doors created with corridors
+    fn create_doorways(&mut self, doors: &[(usize, usize, Door)])
+    {
+        for &(x, y, door) in doors
+        {
+            let tile = self.map[x][y];
+
+            if tile == DungeonTile::TileClosedDoor as u8 || tile == DungeonTile::TileOpenDoor as u8
+            {
+                self.regions[x][y] = Region::Doorway(door);
+            }
+        }
+    }
+
+    /// Puts doors of the dungeon in their doorways again, so changed door states show up on the map.
+    /// Doorways with doors the dungeon doesn't know are left alone
+    /// * 'dungeon' - The dungeon the map was created from
+    pub(crate) fn update_doors(&mut self, dungeon: &Dungeon)
+    {
+        for y in 0..self.map_height
+        {
+            for x in 0..self.map_width
+            {
+                if let Region::Doorway(door) = self.regions[x][y]
+                {
+                    if let Some(d) = dungeon.get_door_by_id(door.id)
+                    {
+                        self.set_door(x, y, d);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn create_map(&mut self, d: &Dungeon) -> &Vec<Vec<u8>>
     {
-        self.create_rooms(&d);
-        self.create_corridors(&d);
+        self.create_rooms(d);
+        let doors = self.create_corridors(d);
         self.remove_redundant_walls();
         self.remove_not_useful_doors();
-        self.create_items(&d);        
+        self.create_doorways(&doors);
+        self.populate(d);
 
         &self.map
     }
-}
\ No newline at end of file
+
+    /// Puts doors, stairs, items, monsters and traps of the dungeon on the created map again, keeping its rooms and corridors.
+    /// Must be called when the dungeon was changed with the map already created, e.g. by lock_doors.
+    /// Props are removed with the old content, so decorate should be called afterwards
+    /// * 'd' - The dungeon the map was created from
+    pub fn populate(&mut self, d: &Dungeon)
+    {
+        const CONTENT_TILES: [DungeonTile; 4] = [DungeonTile::TileChest, DungeonTile::TileKey, DungeonTile::TileStairsUp, DungeonTile::TileStairsDown];
+
+        self.update_doors(d);
+
+        for y in 0..self.map_height
+        {
+            for x in 0..self.map_width
+            {
+                if CONTENT_TILES.iter().any(|&t| self.map[x][y] == t as u8)
+                {
+                    self.map[x][y] = DungeonTile::TileEmpty as u8;
+                }
+
+                if matches!(self.features[x][y], Feature::Trap(_) | Feature::Prop(_))
+                {
+                    self.features[x][y] = Feature::None;
+                }
+            }
+        }
+
+        self.items.clear();
+        self.monsters.clear();
+        self.traps.clear();
+
+        self.create_stairs(d);
+        self.create_items(d);
+        self.create_monsters(d);
+        self.create_traps(d);
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+    use crate::room::Room;
+
+    #[test]    
+    fn region_map_test()
+    {
+        let door = |id| Some(Door { id, locked: false, open: false, secret: false });
+        let rooms = vec![Room::new(0, 2, 2, 6, 5), Room::new(1, 14, 2, 6, 5), Room::new(2, 26, 10, 5, 5)];
+        let corridors = vec![Corridor::new(0, 0, 1, door(0), door(1)), Corridor::new(1, 1, 2, door(2), door(3))];
+        let d = Dungeon::from_parts(rooms, corridors);
+
+        let mut dm = DungeonMap::new(40, 20);
+        dm.create_map(&d);
+
+        for r in 0..d.get_rooms_number()
+        {
+            let room = d.get_room(r).unwrap();
+            let mut cells = 0;
+
+            for y in 0..dm.get_height()
+            {
+                for x in 0..dm.get_width()
+                {
+                    if dm.room_at(x, y) == Some(room.id)
+                    {
+                        assert!(x >= room.x as usize && x < (room.x + room.width) as usize);
+                        assert!(y >= room.y as usize && y < (room.y + room.height) as usize);
+                        cells += 1;
+                    }
+                }
+            }
+
+            assert_eq!(cells, room.width as usize * room.height as usize);
+        }
+
+        //The first corridor leaves the first room through its right wall
+        assert!((2..7).any(|y| dm.corridor_at(8, y) == Some(0) || dm.door_at(8, y).is_some()));
+
+        for y in 0..dm.get_height()
+        {
+            for x in 0..dm.get_width()
+            {
+                if dm.corridor_at(x, y).is_some()
+                {
+                    assert_ne!(dm.get_tile(x, y), Some(DungeonTile::TileWall as u8));
+                }
+
+                if dm.door_at(x, y).is_some()
+                {
+                    let tile = dm.get_tile(x, y).unwrap();
+                    assert!(tile == DungeonTile::TileClosedDoor as u8 || tile == DungeonTile::TileOpenDoor as u8);
+                }
+            }
+        }
+
+        assert_eq!(dm.get_region(40, 0), None);
+    }
+}
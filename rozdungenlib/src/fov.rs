@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+
+use crate::dungeonmap::{DungeonMap, DungeonTile};
+
+/// A slope given as a fraction, so the computation is exact
+#[derive(Clone, Copy)]
+struct Slope
+{
+    num: isize,
+    den: isize,
+}
+
+impl Slope
+{
+    fn new(num: isize, den: isize) -> Self
+    {
+        Self { num, den }
+    }
+}
+
+/// One row of the scanned quadrant
+#[derive(Clone, Copy)]
+struct Row
+{
+    depth: isize,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row
+{
+    /// Gets the first column of the row. Rounds depth * start slope with ties going up
+    fn get_min_col(&self) -> isize
+    {
+        (2 * self.depth * self.start.num + self.start.den).div_euclid(2 * self.start.den)
+    }
+
+    /// Gets the last column of the row. Rounds depth * end slope with ties going down
+    fn get_max_col(&self) -> isize
+    {
+        -(-(2 * self.depth * self.end.num - self.end.den)).div_euclid(2 * self.end.den)
+    }
+
+    /// A floor cell is visible only if its centre lies between the start and the end slope
+    fn is_symmetric(&self, col: isize) -> bool
+    {
+        col * self.start.den >= self.depth * self.start.num
+            && col * self.end.den <= self.depth * self.end.num
+    }
+
+    fn next(&self) -> Self
+    {
+        Self { depth: self.depth + 1, start: self.start, end: self.end }
+    }
+}
+
+/// Gets a slope of the left edge of the cell
+fn get_slope(depth: isize, col: isize) -> Slope
+{
+    Slope::new(2 * col - 1, 2 * depth)
+}
+
+/// Cardinal direction of the scanned quadrant
+#[derive(Clone, Copy)]
+enum Quadrant
+{
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Quadrant
+{
+    /// Turns a quadrant relative position into the map position
+    fn transform(&self, origin: (isize, isize), depth: isize, col: isize) -> (isize, isize)
+    {
+        match self
+        {
+            Quadrant::North => (origin.0 + col, origin.1 - depth),
+            Quadrant::South => (origin.0 + col, origin.1 + depth),
+            Quadrant::East => (origin.0 + depth, origin.1 + col),
+            Quadrant::West => (origin.0 - depth, origin.1 + col)
+        }
+    }
+}
+
+impl DungeonMap
+{
+    /// Returns true if the cell can't be seen through. Walls, closed doors and the area outside
+    /// of the map block sight
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn blocks_sight(&self, x: isize, y: isize) -> bool
+    {
+        if x < 0 || y < 0
+        {
+            return true;
+        }
+
+        match self.get_tile(x as usize, y as usize)
+        {
+            Some(tile) =>
+            {
+                tile == DungeonTile::TileWall as u8
+                    || tile == DungeonTile::TileDummy as u8
+                    || tile == DungeonTile::TileClosedDoor as u8
+            },
+            None => true
+        }
+    }
+
+    /// Scans one quadrant row by row, recursing when a wall splits the row
+    fn scan_row(&self, quadrant: Quadrant, origin: (isize, isize), radius: isize, mut row: Row, visible: &mut HashSet<(usize, usize)>)
+    {
+        if row.depth > radius
+        {
+            return;
+        }
+
+        let mut prev_wall: Option<bool> = None;
+
+        for col in row.get_min_col()..=row.get_max_col()
+        {
+            let (x, y) = quadrant.transform(origin, row.depth, col);
+            let wall = self.blocks_sight(x, y);
+
+            if (wall || row.is_symmetric(col))
+                && x >= 0 && y >= 0
+                && (x - origin.0).pow(2) + (y - origin.1).pow(2) <= radius * radius
+                && self.get_tile(x as usize, y as usize).is_some()
+            {
+                visible.insert((x as usize, y as usize));
+            }
+
+            if prev_wall == Some(true) && !wall
+            {
+                row.start = get_slope(row.depth, col);
+            }
+
+            if prev_wall == Some(false) && wall
+            {
+                let mut next_row = row.next();
+                next_row.end = get_slope(row.depth, col);
+                self.scan_row(quadrant, origin, radius, next_row, visible);
+            }
+
+            prev_wall = Some(wall);
+        }
+
+        if prev_wall == Some(false)
+        {
+            self.scan_row(quadrant, origin, radius, row.next(), visible);
+        }
+    }
+
+    /// Computes cells visible from given position with symmetric recursive shadowcasting.
+    /// If a cell A can see a cell B then B can see A too. Walls blocking the view are visible
+    /// * 'x' - X coordinate of the viewer
+    /// * 'y' - Y coordinate of the viewer
+    /// * 'radius' - How far the viewer can see
+    pub fn compute_fov(&self, x: usize, y: usize, radius: usize) -> HashSet<(usize, usize)>
+    {
+        let mut visible: HashSet<(usize, usize)> = HashSet::new();
+
+        if self.get_tile(x, y).is_none()
+        {
+            return visible;
+        }
+
+        visible.insert((x, y));
+
+        let origin = (x as isize, y as isize);
+        let first_row = Row { depth: 1, start: Slope::new(-1, 1), end: Slope::new(1, 1) };
+
+        for quadrant in [Quadrant::North, Quadrant::East, Quadrant::South, Quadrant::West]
+        {
+            self.scan_row(quadrant, origin, radius as isize, first_row, &mut visible);
+        }
+
+        visible
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::door::Door;
+
+    #[test]
+    fn fov_test()
+    {
+        //Two rooms split by a wall with a door at (5, 2)
+        let mut dm = DungeonMap::new(11, 5);
+        (1..10).for_each(|x| {
+            (1..4).for_each(|y| dm.set_tile(x, y, DungeonTile::TileEmpty as u8));
+        });
+        (1..4).for_each(|y| dm.set_tile(5, y, DungeonTile::TileWall as u8));
+        dm.set_door(5, 2, Door { id: 0, locked: false, open: false, secret: false });
+
+        let visible = dm.compute_fov(2, 2, 10);
+        assert!(visible.contains(&(4, 3)));
+        assert!(visible.contains(&(5, 2)));
+        assert!(visible.contains(&(0, 0)));
+        assert!(!visible.contains(&(7, 2)));
+
+        dm.set_door(5, 2, Door { id: 0, locked: false, open: true, secret: false });
+
+        let visible = dm.compute_fov(2, 2, 10);
+        assert!(visible.contains(&(7, 2)));
+
+        //Symmetry
+        let back = dm.compute_fov(7, 2, 10);
+        assert!(back.contains(&(2, 2)));
+
+        //Radius limits the view
+        let visible = dm.compute_fov(2, 2, 1);
+        assert!(!visible.contains(&(4, 2)));
+    }
+}
@@ -1,4 +1,5 @@
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ItemType
 {
     ///id of the door that key can open
@@ -6,9 +7,53 @@ pub enum ItemType
     Weapon,
     Armor,
     Potion,
+    ///id of a kind defined outside of the library, see ItemKind
+    Custom(u32),
 }
 
-#[derive(Clone, PartialEq)]
+/// Properties of an item used by the game
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemStats
+{
+    pub damage: u32,
+    pub defence: u32,
+    /// Name of the effect the item has when used, e.g. "heal"
+    pub effect: Option<String>,
+    /// Price of the item
+    pub value: u32,
+    pub weight: f32,
+    /// How many items of this kind lie in one place
+    pub stack: u32,
+}
+
+impl Default for ItemStats
+{
+    fn default() -> Self
+    {
+        Self { damage: 0, defence: 0, effect: None, value: 0, weight: 0.0, stack: 1 }
+    }
+}
+
+/// A kind of items defined outside of the library, e.g. scrolls or food.
+/// Items of such kinds are stored as ItemType::Custom holding the kind id
+pub trait ItemKind
+{
+    /// Gets an unique id of the kind
+    fn get_kind_id(&self) -> u32;
+
+    /// Gets a name of the kind, also used in loot files
+    fn get_kind_name(&self) -> &str;
+
+    /// Gets stats new items of the kind get
+    fn get_default_stats(&self) -> ItemStats
+    {
+        ItemStats::default()
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Item
 {
     /// An unique id of the item
@@ -17,12 +62,33 @@ pub struct Item
     pub item_type: ItemType,
     /// Description of the item
     pub desc: String,
+    /// Properties of the item
+    pub stats: ItemStats,
 }
 
 impl Item
 {
-    pub fn new(iid: usize, it: ItemType, d: &String) -> Self
+    pub fn new(iid: usize, it: ItemType, d: &str) -> Self
     {
-        Self { id: iid, item_type: it, desc: d.clone() }
+        Self { id: iid, item_type: it, desc: d.to_string(), stats: ItemStats::default() }
     }
-}
\ No newline at end of file
+
+    /// Creates an item with given stats
+    /// * 'iid' - An unique id of the item
+    /// * 'it' - Type of the item
+    /// * 'd' - Description of the item
+    /// * 's' - Properties of the item
+    pub fn new_with_stats(iid: usize, it: ItemType, d: &str, s: ItemStats) -> Self
+    {
+        Self { id: iid, item_type: it, desc: d.to_string(), stats: s }
+    }
+
+    /// Creates an item of a kind defined outside of the library with the default stats of the kind
+    /// * 'iid' - An unique id of the item
+    /// * 'kind' - The kind of the item
+    /// * 'd' - Description of the item
+    pub fn from_kind(iid: usize, kind: &dyn ItemKind, d: &str) -> Self
+    {
+        Self::new_with_stats(iid, ItemType::Custom(kind.get_kind_id()), d, kind.get_default_stats())
+    }
+}
@@ -3,4 +3,28 @@ pub mod dungeon;
 pub mod corridor;
 pub mod dungeonmap;
 pub mod door;
-pub mod item;
\ No newline at end of file
+pub mod item;
+pub mod connectivity;
+pub mod pathfinding;
+pub mod dijkstra;
+pub mod fov;
+pub mod light;
+pub mod raycast;
+pub mod ascii;
+#[cfg(feature = "png")]
+pub mod render;
+pub mod tiled;
+pub mod dot;
+pub mod svg;
+pub mod transform;
+pub mod roomgraph;
+pub mod lock;
+pub mod loot;
+pub mod monster;
+pub mod difficulty;
+pub mod stairs;
+pub mod roles;
+pub mod trap;
+pub mod secret;
+pub mod decoration;
+mod xml;
\ No newline at end of file
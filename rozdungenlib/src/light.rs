@@ -0,0 +1,174 @@
+use crate::dungeon::Dungeon;
+use crate::dungeonmap::DungeonMap;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LightKind
+{
+    Torch,          //Small light hanging by the wall
+    Brazier,        //Big fire in the middle of the chamber
+    GlowingItem,    //Weak light of a magic item
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LightSource
+{
+    pub kind: LightKind,
+    /// X position in the dungeon
+    pub x: u16,
+    /// Y position in the dungeon
+    pub y: u16,
+    /// How far the light reaches
+    pub radius: u16,
+    /// Strength of the light from 0.0 to 1.0
+    pub intensity: f32,
+    /// Colour of the light as RGB
+    pub colour: (u8, u8, u8),
+}
+
+impl LightSource
+{
+    /// Creates a light with the radius, the intensity and the colour typical for its kind
+    pub fn new(kind: LightKind, xp: u16, yp: u16) -> Self
+    {
+        let (radius, intensity, colour) = match kind
+        {
+            LightKind::Torch => (5, 0.8, (255, 180, 100)),
+            LightKind::Brazier => (8, 1.0, (255, 140, 60)),
+            LightKind::GlowingItem => (2, 0.5, (120, 160, 255))
+        };
+
+        Self { kind, x: xp, y: yp, radius, intensity, colour }
+    }
+}
+
+/// Amount of light falling on every map cell
+#[derive(Clone)]
+pub struct LightMap
+{
+    width: usize,
+    height: usize,
+    cells: Vec<Vec<[f32; 3]>>,
+}
+
+impl LightMap
+{
+    /// Gets the light at given position as RGB values from 0.0 to 1.0
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn get_light(&self, x: usize, y: usize) -> Option<(f32, f32, f32)>
+    {
+        if x >= self.width || y >= self.height
+        {
+            return None;
+        }
+
+        let c = self.cells[x][y];
+
+        Some((c[0], c[1], c[2]))
+    }
+
+    /// Gets the brightness of the cell, the strongest of its colour components
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn get_brightness(&self, x: usize, y: usize) -> f32
+    {
+        match self.get_light(x, y)
+        {
+            Some((r, g, b)) => r.max(g).max(b),
+            None => 0.0
+        }
+    }
+
+    /// Returns true if the cell is brighter than given threshold
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    /// * 'threshold' - The lowest brightness the cell is treated as lit
+    pub fn is_lit(&self, x: usize, y: usize, threshold: f32) -> bool
+    {
+        self.get_brightness(x, y) > threshold
+    }
+}
+
+impl DungeonMap
+{
+    /// Computes the light map from lights of all rooms. The light doesn't go through walls and
+    /// closed doors and gets weaker with the distance. Rooms with no lights get only the ambient light
+    /// * 'dungeon' - The dungeon the map was created from
+    /// * 'ambient' - Light level of every cell from 0.0 to 1.0
+    pub fn compute_light_map(&self, dungeon: &Dungeon, ambient: f32) -> LightMap
+    {
+        let width = self.get_width();
+        let height = self.get_height();
+        let mut cells = vec![vec![[ambient; 3]; height]; width];
+
+        for r in 0..dungeon.get_rooms_number()
+        {
+            let room = dungeon.get_room(r).unwrap();
+
+            for light in room.lights.iter()
+            {
+                let (lx, ly) = (light.x as usize, light.y as usize);
+                let colour = [light.colour.0, light.colour.1, light.colour.2];
+
+                for (x, y) in self.compute_fov(lx, ly, light.radius as usize)
+                {
+                    let distance = ((x.abs_diff(lx).pow(2) + y.abs_diff(ly).pow(2)) as f32).sqrt();
+                    let falloff = 1.0 - distance / (light.radius as f32 + 1.0);
+                    let cell = &mut cells[x][y];
+
+                    (0..3).for_each(|i| {
+                        cell[i] = (cell[i] + colour[i] as f32 / 255.0 * light.intensity * falloff).min(1.0);
+                    });
+                }
+            }
+        }
+
+        LightMap { width, height, cells }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::ascii::GlyphTable;
+    use crate::room::Room;
+
+    const LEVEL: &str = "\
+###########
+#.........#
+#...#.....#
+#.........#
+###########
+";
+
+    #[test]
+    fn compute_light_map_test()
+    {
+        let dm = DungeonMap::from_ascii(LEVEL, &GlyphTable::default()).unwrap();
+        let mut room = Room::new(0, 1, 1, 9, 3);
+        room.lights.push(LightSource { kind: LightKind::Torch, x: 2, y: 2, radius: 4, intensity: 1.0, colour: (255, 255, 255) });
+
+        let d = Dungeon::from_parts(vec![room], Vec::new());
+        let light = dm.compute_light_map(&d, 0.1);
+
+        //The light is the strongest at its source and gets weaker with the distance
+        assert_eq!(light.get_light(2, 2), Some((1.0, 1.0, 1.0)));
+        assert!((light.get_brightness(3, 2) - 0.9).abs() < 1e-6);
+        assert!(light.get_brightness(3, 2) > light.get_brightness(3, 1));
+        assert_eq!(light.get_brightness(3, 1), light.get_brightness(3, 3));
+        assert!(light.get_brightness(1, 1) > light.get_brightness(6, 1));
+
+        //The wall casts a shadow and the far end of the room is out of reach
+        assert!(light.is_lit(4, 2, 0.1));
+        assert_eq!(light.get_brightness(5, 2), 0.1);
+        assert_eq!(light.get_brightness(6, 2), 0.1);
+        assert_eq!(light.get_brightness(9, 2), 0.1);
+        assert!(!light.is_lit(5, 2, 0.1));
+
+        assert_eq!(light.get_light(11, 0), None);
+        assert_eq!(light.get_brightness(11, 0), 0.0);
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+
+use rand::{thread_rng, Rng};
+use rand::seq::SliceRandom;
+
+use crate::connectivity::DoorPassage;
+use crate::door::Door;
+use crate::dungeon::Dungeon;
+use crate::dungeonmap::{DungeonMap, Region};
+use crate::item::{Item, ItemType};
+
+impl Dungeon
+{
+    /// Gets an id not used by any item yet
+    pub(crate) fn get_next_item_id(&self) -> usize
+    {
+        (0..self.get_rooms_number())
+            .flat_map(|r| self.get_room(r).unwrap().items.iter())
+            .map(|i| i.id + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Gets all doors of the dungeon with ids of the rooms on both ends of their corridors
+    fn get_doors(&self) -> Vec<(Door, usize, usize)>
+    {
+        (0..self.get_corridors_number())
+            .map(|c| self.get_corridor(c).unwrap())
+            .flat_map(|c| [c.from_room_door, c.to_room_door].into_iter().flatten().map(move |d| (d, c.from_room_id, c.to_room_id)))
+            .collect()
+    }
+
+    /// Gets a door by its id
+    fn get_door_mut(&mut self, door_id: usize) -> Option<&mut Door>
+    {
+        (0..self.get_corridors_number()).find(|&c| {
+            let corridor = self.get_corridor(c).unwrap();
+
+            [corridor.from_room_door, corridor.to_room_door].iter().flatten().any(|d| d.id == door_id)
+        })
+        .and_then(|c| {
+            let corridor = self.get_corridor_mut(c).unwrap();
+
+            [&mut corridor.from_room_door, &mut corridor.to_room_door].into_iter().flatten().find(|d| d.id == door_id)
+        })
+    }
+
+    /// Plays through the dungeon picking up keys. Returns reached rooms with the number of times
+    /// new keys had to be picked up before the room could be entered
+    /// * 'start_room_id' - Id of the room the player starts in
+    /// * 'extra_locked' - Id of a door treated as locked with no key for it
+    pub(crate) fn explore(&self, start_room_id: usize, extra_locked: Option<usize>) -> HashMap<usize, usize>
+    {
+        let mut layers: HashMap<usize, usize> = HashMap::new();
+        let mut keys: Vec<usize> = Vec::new();
+        let mut layer = 0;
+
+        loop
+        {
+            let mut new_keys = false;
+
+            for room_id in self.find_reachable_rooms(start_room_id, &keys, extra_locked)
+            {
+                layers.entry(room_id).or_insert(layer);
+
+                for item in self.get_room_by_id(room_id).unwrap().items.iter()
+                {
+                    if let ItemType::Key(door_id) = item.item_type
+                    {
+                        if !keys.contains(&door_id)
+                        {
+                            keys.push(door_id);
+                            new_keys = true;
+                        }
+                    }
+                }
+            }
+
+            if !new_keys
+            {
+                return layers;
+            }
+
+            layer += 1;
+        }
+    }
+
+    /// Returns true if the player can enter every room connected with the start room,
+    /// because all keys lie before the doors they open
+    pub fn is_solvable(&self) -> bool
+    {
+        match self.get_start_room_id()
+        {
+            Some(start) => self.explore(start, None).len() == self.get_room_graph_distances(start).len(),
+            None => true
+        }
+    }
+
+    /// Puts a key for the door in the room
+    fn put_key(&mut self, door_id: usize, room_id: usize, item_id: &mut usize)
+    {
+        let idx = (0..self.get_rooms_number()).find(|&r| self.get_room(r).unwrap().id == room_id).unwrap();
+
+        self.get_room_mut(idx).unwrap().items.push(Item::new(*item_id, ItemType::Key(door_id), &format!("Key to door {}", door_id)));
+        *item_id += 1;
+    }
+
+    /// Gets rooms the player can enter on the map from the start cell when only the given door is locked.
+    /// Other doors are treated as unlocked, secret doors as walls
+    fn get_map_reachable_rooms(&self, map: &DungeonMap, start: (usize, usize), locked: Option<usize>) -> Vec<usize>
+    {
+        let mut map = map.clone();
+        map.update_doors(self);
+
+        (0..map.get_height()).for_each(|y| {
+            (0..map.get_width()).for_each(|x| {
+                if let Some(door) = map.door_at(x, y)
+                {
+                    map.set_door(x, y, Door { locked: Some(door.id) == locked, ..door });
+                }
+            });
+        });
+
+        let report = match map.validate_connectivity(start.0, start.1, DoorPassage::Unlocked)
+        {
+            Ok(r) => r,
+            Err(_) => return Vec::new()
+        };
+
+        let mut rooms: Vec<usize> = Vec::new();
+
+        (0..map.get_height()).for_each(|y| {
+            (0..map.get_width()).for_each(|x| {
+                if let Some(Region::Room(id)) = map.get_region(x, y)
+                {
+                    if report.is_reachable(x, y) && !rooms.contains(&id)
+                    {
+                        rooms.push(id);
+                    }
+                }
+            });
+        });
+
+        rooms
+    }
+
+    /// Locks doors and puts a key for every locked door in a room the player can reach before the door,
+    /// so the dungeon can always be completed. Only doors standing on the map which cut off some rooms
+    /// of the map are locked. Returns the number of locked doors which can be lower than requested
+    /// if there are not enough doors worth locking. Secret doors are never locked.
+    /// Must be called after add_doors with the map created from the dungeon. Call DungeonMap::populate
+    /// afterwards to put the locked doors and the keys on the map
+    /// * 'map' - The map created from the dungeon
+    /// * 'count' - Number of doors to lock
+    /// * 'nested' - If true the doors make a chain: every next door and its key lie behind the previous door
+    pub fn lock_doors(&mut self, map: &DungeonMap, count: usize, nested: bool) -> Result<usize, String>
+    {
+        let start = self.get_start_room_id().ok_or("There are no rooms in the dungeon!")?;
+        let start_cell = self.get_start_cell(map).ok_or("The start room is not on the map!")?;
+        let all_rooms = self.get_map_reachable_rooms(map, start_cell, None);
+        let mut rng = thread_rng();
+        let mut item_id = self.get_next_item_id();
+        let mut locked = 0;
+
+        for _ in 0..count
+        {
+            let layers = self.explore(start, None);
+            let deepest = layers.values().max().copied().unwrap_or(0);
+
+            //A door is worth locking only if it cuts off some rooms, both in the room graph and on the map.
+            //Keys can go only to rooms reachable before the door in both of them
+            let candidates: Vec<(usize, HashMap<usize, usize>, bool)> = self.get_doors().into_iter()
+                .filter(|(d, from, to)| !d.locked && !d.secret && (layers.contains_key(from) || layers.contains_key(to)))
+                .filter_map(|(d, from, to)| {
+                    let reachable = self.get_map_reachable_rooms(map, start_cell, Some(d.id));
+                    let mut cut = self.explore(start, Some(d.id));
+                    let deep = layers.get(&from) == Some(&deepest) || layers.get(&to) == Some(&deepest);
+
+                    if cut.len() == layers.len() || reachable.len() == all_rooms.len()
+                    {
+                        return None;
+                    }
+
+                    cut.retain(|r, _| reachable.contains(r));
+
+                    (!cut.is_empty()).then_some((d.id, cut, deep))
+                })
+                .collect();
+
+            let deep_candidates: Vec<&(usize, HashMap<usize, usize>, bool)> = candidates.iter().filter(|c| c.2).collect();
+
+            let chosen = match nested && !deep_candidates.is_empty()
+            {
+                true => deep_candidates.choose(&mut rng).copied(),
+                false => candidates.choose(&mut rng)
+            };
+
+            let (door_id, cut, _) = match chosen
+            {
+                Some(c) => c.clone(),
+                None => break
+            };
+
+            let key_rooms: Vec<usize> = match nested
+            {
+                true =>
+                {
+                    let max_layer = cut.values().max().copied().unwrap_or(0);
+                    cut.iter().filter(|&(_, &l)| l == max_layer).map(|(&r, _)| r).collect()
+                },
+                false => cut.keys().copied().collect()
+            };
+
+            let key_room = key_rooms[rng.gen_range(0..key_rooms.len())];
+            let door = self.get_door_mut(door_id).unwrap();
+
+            door.locked = true;
+            door.open = false;
+
+            self.put_key(door_id, key_room, &mut item_id);
+            locked += 1;
+        }
+
+        Ok(locked)
+    }
+
+    /// Puts keys for locked doors with no key in rooms reachable before the doors
+    pub(crate) fn add_missing_keys(&mut self, item_id: &mut usize)
+    {
+        let start = match self.get_start_room_id()
+        {
+            Some(s) => s,
+            None => return
+        };
+
+        let mut rng = thread_rng();
+
+        loop
+        {
+            let keys: Vec<usize> = (0..self.get_rooms_number())
+                .flat_map(|r| self.get_room(r).unwrap().items.iter())
+                .filter_map(|i| match i.item_type
+                {
+                    ItemType::Key(door_id) => Some(door_id),
+                    _ => None
+                })
+                .collect();
+
+            let layers = self.explore(start, None);
+            let door = self.get_doors().into_iter()
+                .find(|(d, from, to)| d.locked && !keys.contains(&d.id) && (layers.contains_key(from) || layers.contains_key(to)));
+
+            match door
+            {
+                Some((d, _, _)) =>
+                {
+                    let rooms: Vec<usize> = layers.keys().copied().collect();
+                    let room_id = rooms[rng.gen_range(0..rooms.len())];
+
+                    self.put_key(d.id, room_id, item_id);
+                },
+                None => return
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::corridor::Corridor;
+    use crate::dijkstra::DijkstraMap;
+    use crate::dungeon::DungeonType;
+    use crate::pathfinding::PathSettings;
+    use crate::room::Room;
+
+    fn get_keys(d: &Dungeon) -> Vec<usize>
+    {
+        (0..d.get_rooms_number())
+            .flat_map(|r| d.get_room(r).unwrap().items.iter())
+            .filter_map(|i| match i.item_type
+            {
+                ItemType::Key(door_id) => Some(door_id),
+                _ => None
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lock_doors_test()
+    {
+        for nested in [false, true]
+        {
+            let mut d = Dungeon::new();
+            d.generate(12, DungeonType::Basement, 80, 80, 8, 8).unwrap();
+            d.add_doors().unwrap();
+
+            let mut dm = DungeonMap::new(80, 80);
+            dm.create_map(&d);
+
+            let locked = d.lock_doors(&dm, 3, nested).unwrap();
+            let mut locked_doors: Vec<usize> = d.get_doors().iter().filter(|(door, _, _)| door.locked).map(|(door, _, _)| door.id).collect();
+            let mut keys = get_keys(&d);
+
+            keys.sort();
+            locked_doors.sort();
+            assert_eq!(locked_doors.len(), locked);
+            assert_eq!(keys, locked_doors);
+            assert!(d.is_solvable());
+
+            d.add_items(true);
+            dm.populate(&d);
+
+            assert_eq!(get_keys(&d).len(), locked);
+            assert!(d.is_solvable());
+
+            //Every locked door stands on the map and cuts it on its own
+            let start = d.get_start_cell(&dm).unwrap();
+            let all_rooms = d.get_map_reachable_rooms(&dm, start, None);
+
+            for &door_id in locked_doors.iter()
+            {
+                assert!((0..80 * 80).any(|i| dm.door_at(i % 80, i / 80).is_some_and(|door| door.id == door_id && door.locked)));
+                assert!(d.get_map_reachable_rooms(&dm, start, Some(door_id)).len() < all_rooms.len());
+            }
+
+            //Picking up keys on the map opens the way to every room
+            let mut settings = PathSettings::default();
+
+            loop
+            {
+                let distances = DijkstraMap::new(&dm, &[start], &settings);
+                let found: Vec<usize> = dm.get_item_placements().iter()
+                    .filter(|p| distances.get_value(p.x, p.y).is_some())
+                    .filter_map(|p| match d.get_item_by_id(p.item_id).unwrap().item_type
+                    {
+                        ItemType::Key(door_id) if !settings.keys.contains(&door_id) => Some(door_id),
+                        _ => None
+                    })
+                    .collect();
+
+                if found.is_empty()
+                {
+                    assert_eq!(distances.get_room_distances(&dm).len(), all_rooms.len());
+                    break;
+                }
+
+                settings.keys.extend(found);
+            }
+        }
+    }
+
+    #[test]
+    fn lock_doors_fixed_map_test()
+    {
+        //Three rooms in a row, each corridor has a door on the left end
+        let door = |id| Some(Door { id, locked: false, open: false, secret: false });
+        let rooms = vec![Room::new(0, 2, 2, 4, 4), Room::new(1, 12, 2, 4, 4), Room::new(2, 22, 2, 4, 4)];
+        let mut d = Dungeon::from_parts(rooms, vec![Corridor::new(0, 0, 1, door(0), None), Corridor::new(1, 1, 2, door(1), None)]);
+
+        let mut dm = DungeonMap::new(30, 10);
+        dm.create_map(&d);
+
+        assert_eq!(d.lock_doors(&dm, 3, true).unwrap(), 2);
+
+        //The key to the first door lies in the start room, the other one behind the first door
+        dm.populate(&d);
+        let key_room = |door_id| dm.get_item_placements().iter()
+            .find(|p| d.get_item_by_id(p.item_id).unwrap().item_type == ItemType::Key(door_id))
+            .map(|p| p.room_id);
+
+        assert_eq!(key_room(0), Some(0));
+        assert!(key_room(1) == Some(0) || key_room(1) == Some(1));
+        assert_eq!((0..30 * 10).filter(|i| dm.door_at(i % 30, i / 30).is_some_and(|door| door.locked)).count(), 2);
+    }
+}
\ No newline at end of file
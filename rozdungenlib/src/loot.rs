@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use rand::{thread_rng, Rng};
+
+use crate::dungeon::Dungeon;
+use crate::item::{Item, ItemKind, ItemStats, ItemType};
+
+/// Name of the table used for rooms with no table of their own
+pub const DEFAULT_LOOT_TABLE: &str = "default";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rarity
+{
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+impl Rarity
+{
+    /// Gets a rarity from its name
+    /// * 'name' - The name, e.g. "Rare"
+    pub fn from_name(name: &str) -> Option<Self>
+    {
+        match name
+        {
+            "Common" => Some(Rarity::Common),
+            "Uncommon" => Some(Rarity::Uncommon),
+            "Rare" => Some(Rarity::Rare),
+            "Epic" => Some(Rarity::Epic),
+            "Legendary" => Some(Rarity::Legendary),
+            _ => None
+        }
+    }
+
+    /// Gets the weight of an entry at given depth. Rarer items get more likely the deeper the dungeon is:
+    /// each rarity tier above common adds 10% of the weight per depth level. Weights too big for u32 are capped
+    /// * 'weight' - The weight of the entry
+    /// * 'depth' - Depth of the dungeon
+    pub fn scale_weight(&self, weight: u32, depth: u32) -> u32
+    {
+        let tier = *self as u64;
+        let scaled = (weight as u64).saturating_mul(10 + tier * depth as u64) / 10;
+
+        u32::try_from(scaled).unwrap_or(u32::MAX)
+    }
+}
+
+/// One kind of item that can drop from a loot table
+#[derive(Clone, PartialEq, Debug)]
+pub struct LootEntry
+{
+    pub item_type: ItemType,
+    /// Name given to the item
+    pub name: String,
+    /// How often the entry is chosen compared to other entries
+    pub weight: u32,
+    pub rarity: Rarity,
+    /// The lowest dungeon depth the entry drops at
+    pub min_depth: u32,
+    /// The highest dungeon depth the entry drops at
+    pub max_depth: u32,
+    /// If true the entry drops in every room using the table, apart from the random drops
+    pub guaranteed: bool,
+    /// Stats given to dropped items
+    pub stats: ItemStats,
+}
+
+/// Weighted list of items put in rooms
+#[derive(Clone, PartialEq, Debug)]
+pub struct LootTable
+{
+    /// The lowest number of random drops per room
+    pub min_rolls: u32,
+    /// The highest number of random drops per room
+    pub max_rolls: u32,
+    pub entries: Vec<LootEntry>,
+}
+
+impl LootTable
+{
+    pub fn new(min_rolls: u32, max_rolls: u32) -> Self
+    {
+        Self { min_rolls, max_rolls: max_rolls.max(min_rolls), entries: Vec::new() }
+    }
+
+    /// Chooses items for one room: all guaranteed entries and a random number of weighted drops.
+    /// Entries not meant for the depth are skipped
+    /// * 'depth' - Depth of the dungeon
+    pub fn roll(&self, depth: u32) -> Vec<&LootEntry>
+    {
+        let mut drops: Vec<&LootEntry> = self.entries.iter()
+            .filter(|e| e.guaranteed && e.min_depth <= depth && depth <= e.max_depth)
+            .collect();
+
+        drops.extend(self.roll_drops(depth, thread_rng().gen_range(self.min_rolls..=self.max_rolls)));
+
+        drops
+    }
+
+    /// Chooses given number of weighted drops, guaranteed entries are not added
+    /// * 'depth' - Depth of the dungeon
+    /// * 'rolls' - Number of drops
+    pub fn roll_drops(&self, depth: u32, rolls: u32) -> Vec<&LootEntry>
+    {
+        let mut rng = thread_rng();
+        let available: Vec<&LootEntry> = self.entries.iter().filter(|e| e.min_depth <= depth && depth <= e.max_depth).collect();
+        let weights: Vec<u64> = available.iter().map(|e| e.rarity.scale_weight(e.weight, depth) as u64).collect();
+        let total: u64 = weights.iter().sum();
+        let mut drops: Vec<&LootEntry> = Vec::new();
+
+        if total > 0
+        {
+            (0..rolls).for_each(|_| {
+                let mut pick = rng.gen_range(0..total);
+
+                for (entry, &weight) in available.iter().zip(weights.iter())
+                {
+                    if pick < weight
+                    {
+                        drops.push(entry);
+                        break;
+                    }
+
+                    pick -= weight;
+                }
+            });
+        }
+
+        drops
+    }
+}
+
+/// Loot tables by their names
+#[derive(Clone, PartialEq, Debug)]
+pub struct LootTables
+{
+    tables: HashMap<String, LootTable>,
+}
+
+impl Default for LootTables
+{
+    /// Creates the default table with weapons, armors and potions all equally likely
+    fn default() -> Self
+    {
+        let mut table = LootTable::new(0, 2);
+
+        for (item_type, name) in [(ItemType::Weapon, "Weapon"), (ItemType::Armor, "Armor"), (ItemType::Potion, "Potion")]
+        {
+            table.entries.push(LootEntry { item_type, name: name.to_string(), weight: 1, rarity: Rarity::Common,
+                min_depth: 0, max_depth: u32::MAX, guaranteed: false, stats: ItemStats::default() });
+        }
+
+        let mut tables = Self::new();
+        tables.add_table(DEFAULT_LOOT_TABLE, table);
+
+        tables
+    }
+}
+
+/// Parses an item type name used in loot files. Returns the type and the default stats of its items
+fn parse_item_type(name: &str, kinds: &[&dyn ItemKind]) -> Option<(ItemType, ItemStats)>
+{
+    match name
+    {
+        "Weapon" => Some((ItemType::Weapon, ItemStats::default())),
+        "Armor" => Some((ItemType::Armor, ItemStats::default())),
+        "Potion" => Some((ItemType::Potion, ItemStats::default())),
+        _ => kinds.iter()
+            .find(|k| k.get_kind_name() == name)
+            .map(|k| (ItemType::Custom(k.get_kind_id()), k.get_default_stats()))
+    }
+}
+
+/// Sets one of item stats written as "name=value"
+fn parse_stat(stats: &mut ItemStats, text: &str) -> Result<(), String>
+{
+    let (name, value) = text.split_once('=').ok_or(format!("Unknown flag '{}'!", text))?;
+    let value = value.trim();
+    let wrong_value = || format!("Wrong value of '{}'!", name.trim());
+
+    match name.trim()
+    {
+        "damage" => stats.damage = value.parse().map_err(|_| wrong_value())?,
+        "defence" => stats.defence = value.parse().map_err(|_| wrong_value())?,
+        "effect" => stats.effect = Some(value.to_string()),
+        "value" => stats.value = value.parse().map_err(|_| wrong_value())?,
+        "weight" => stats.weight = value.parse().map_err(|_| wrong_value())?,
+        "stack" => stats.stack = value.parse().map_err(|_| wrong_value())?,
+        n => return Err(format!("Unknown stat '{}'!", n))
+    }
+
+    Ok(())
+}
+
+/// Parses a range written as "min-max"
+fn parse_range(text: &str) -> Option<(u32, u32)>
+{
+    let (min, max) = text.split_once('-')?;
+
+    Some((min.trim().parse().ok()?, max.trim().parse().ok()?))
+}
+
+impl LootTables
+{
+    /// Creates an empty set of tables
+    pub fn new() -> Self
+    {
+        Self { tables: HashMap::new() }
+    }
+
+    /// Adds a table or replaces a table with the same name
+    /// * 'name' - Name of the table
+    /// * 'table' - The table
+    pub fn add_table(&mut self, name: &str, table: LootTable)
+    {
+        self.tables.insert(name.to_string(), table);
+    }
+
+    /// Gets a table by its name. If there is no such table the default one is returned
+    /// * 'name' - Name of the table
+    pub fn get_table(&self, name: &str) -> Option<&LootTable>
+    {
+        self.tables.get(name).or_else(|| self.tables.get(DEFAULT_LOOT_TABLE))
+    }
+
+    /// Reads tables from text. Every table starts with its name in square brackets, followed by
+    /// the number of random drops per room and one line per entry:
+    ///
+    /// ```text
+    /// # Comment
+    /// [default]
+    /// rolls = 0-2
+    /// Weapon, Rusty Sword, 50, Common, 1-3
+    /// Potion, Healing Potion, 10, Common, 1-99, guaranteed, effect=heal, value=25
+    /// ```
+    ///
+    /// Entry columns are: item type, name, weight, rarity and depth range. They can be followed by
+    /// the "guaranteed" flag and item stats: damage, defence, effect, value, weight and stack
+    /// * 'text' - The text to read
+    /// * 'kinds' - Item kinds defined outside of the library, used by their names
+    pub fn from_text(text: &str, kinds: &[&dyn ItemKind]) -> Result<Self, String>
+    {
+        let mut tables = Self::new();
+        let mut current: Option<(String, LootTable)> = None;
+
+        for (n, line) in text.lines().enumerate()
+        {
+            let line = line.trim();
+            let err = |msg: &str| format!("Line {}: {}", n + 1, msg);
+
+            if line.is_empty() || line.starts_with('#')
+            {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']'))
+            {
+                if let Some((name, table)) = current.take()
+                {
+                    tables.add_table(&name, table);
+                }
+
+                current = Some((name.trim().to_string(), LootTable::new(0, 1)));
+
+                continue;
+            }
+
+            let (_, table) = current.as_mut().ok_or_else(|| err("Entry outside of a table!"))?;
+
+            if let Some(rolls) = line.strip_prefix("rolls")
+            {
+                let rolls = rolls.trim_start().strip_prefix('=').ok_or_else(|| err("Expected '=' after 'rolls'!"))?;
+                let (min, max) = parse_range(rolls).ok_or_else(|| err("Wrong number of rolls!"))?;
+
+                table.min_rolls = min;
+                table.max_rolls = max.max(min);
+
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+
+            if fields.len() < 5
+            {
+                return Err(err("An entry must have at least 5 columns!"));
+            }
+
+            let (item_type, mut stats) = parse_item_type(fields[0], kinds).ok_or_else(|| err(&format!("Unknown item type '{}'!", fields[0])))?;
+            let weight = fields[2].parse().map_err(|_| err(&format!("Wrong weight '{}'!", fields[2])))?;
+            let rarity = Rarity::from_name(fields[3]).ok_or_else(|| err(&format!("Unknown rarity '{}'!", fields[3])))?;
+            let (min_depth, max_depth) = parse_range(fields[4]).ok_or_else(|| err(&format!("Wrong depth range '{}'!", fields[4])))?;
+            let mut guaranteed = false;
+
+            for field in fields[5..].iter()
+            {
+                match *field
+                {
+                    "guaranteed" => guaranteed = true,
+                    f => parse_stat(&mut stats, f).map_err(|e| err(&e))?
+                }
+            }
+
+            table.entries.push(LootEntry { item_type, name: fields[1].to_string(), weight, rarity, min_depth, max_depth, guaranteed, stats });
+        }
+
+        if let Some((name, table)) = current
+        {
+            tables.add_table(&name, table);
+        }
+
+        Ok(tables)
+    }
+
+    /// Reads tables from a file, see from_text for the file format
+    /// * 'path' - Path to the file
+    /// * 'kinds' - Item kinds defined outside of the library, used by their names
+    pub fn load(path: &Path, kinds: &[&dyn ItemKind]) -> Result<Self, String>
+    {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        Self::from_text(&text, kinds).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl Dungeon
+{
+    /// Populates chambers with items from loot tables. Every room uses the table named after its role,
+    /// e.g. "treasure_vault", or the default table if there is no such table
+    /// * 'tables' - The loot tables
+    /// * 'depth' - Depth of the dungeon, deeper dungeons have more rare items
+    pub fn add_loot(&mut self, tables: &LootTables, depth: u32) -> Result<(), String>
+    {
+        tables.get_table(DEFAULT_LOOT_TABLE).ok_or("There is no default loot table!")?;
+
+        for r in 0..self.get_rooms_number()
+        {
+            let room = self.get_room(r).unwrap();
+            let table = tables.get_table(room.role.get_name()).unwrap();
+
+            self.add_loot_to_room(room.id, table, depth)?;
+        }
+
+        Ok(())
+    }
+
+    /// Puts items from given loot table in one room. Lets the caller choose the table for every room,
+    /// e.g. a boss table for the room with the exit
+    /// * 'room_id' - Id of the room
+    /// * 'table' - The loot table
+    /// * 'depth' - Depth of the dungeon, deeper dungeons have more rare items
+    pub fn add_loot_to_room(&mut self, room_id: usize, table: &LootTable, depth: u32) -> Result<(), String>
+    {
+        let item_id = self.get_next_item_id();
+        let room = self.get_room_by_id_mut(room_id).ok_or(format!("There is no room with id {}!", room_id))?;
+
+        for (id, entry) in (item_id..).zip(table.roll(depth))
+        {
+            room.items.push(Item::new_with_stats(id, entry.item_type, &entry.name, entry.stats.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::dungeon::DungeonType;
+    use crate::room::Room;
+
+    #[test]
+    fn loot_tables_test()
+    {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../resources/loot.txt");
+        let tables = LootTables::load(&path, &[]).unwrap();
+        let table = tables.get_table("no such table").unwrap();
+
+        assert_eq!(table, tables.get_table(DEFAULT_LOOT_TABLE).unwrap());
+        assert!(table.roll(1).iter().any(|e| e.guaranteed));
+        assert!(table.roll(1).iter().all(|e| e.min_depth <= 1 && 1 <= e.max_depth));
+
+        assert!(LootTables::from_text("Weapon, Sword, 1, Common, 1-2", &[]).is_err());
+        assert!(LootTables::from_text("[t]\nWeapon, Sword, 1, Shiny, 1-2", &[]).is_err());
+        assert!(LootTables::from_text("[t]\nrolls = 1\n", &[]).is_err());
+
+        let mut d = Dungeon::new();
+        d.generate(5, DungeonType::Basement, 40, 40, 8, 8).unwrap();
+        d.add_loot(&tables, 1).unwrap();
+
+        (0..d.get_rooms_number()).for_each(|r| assert!(!d.get_room(r).unwrap().items.is_empty()));
+        assert!(d.add_loot(&LootTables::new(), 1).is_err());
+    }
+
+    struct Scroll;
+
+    impl ItemKind for Scroll
+    {
+        fn get_kind_id(&self) -> u32
+        {
+            1
+        }
+
+        fn get_kind_name(&self) -> &str
+        {
+            "Scroll"
+        }
+
+        fn get_default_stats(&self) -> ItemStats
+        {
+            ItemStats { effect: Some("teleport".to_string()), weight: 0.1, ..ItemStats::default() }
+        }
+    }
+
+    #[test]
+    fn custom_kind_test()
+    {
+        let text = "[default]\nrolls = 1-1\nScroll, Scroll of Teleport, 1, Rare, 0-9, value=40, stack=3\n";
+        let tables = LootTables::from_text(text, &[&Scroll]).unwrap();
+        let entry = &tables.get_table(DEFAULT_LOOT_TABLE).unwrap().entries[0];
+
+        assert_eq!(entry.item_type, ItemType::Custom(1));
+        assert_eq!(entry.stats, ItemStats { effect: Some("teleport".to_string()), value: 40, weight: 0.1, stack: 3, ..ItemStats::default() });
+
+        assert!(LootTables::from_text(text, &[]).is_err());
+        assert!(LootTables::from_text("[t]\nWeapon, Sword, 1, Common, 1-2, sharpness=3", &[]).is_err());
+
+        let item = Item::from_kind(7, &Scroll, "Old scroll");
+        assert_eq!(item.item_type, ItemType::Custom(1));
+        assert_eq!(item.stats.effect.as_deref(), Some("teleport"));
+    }
+
+    #[test]
+    fn rarity_scaling_test()
+    {
+        assert_eq!(Rarity::Common.scale_weight(10, 5), 10);
+        assert_eq!(Rarity::Rare.scale_weight(10, 0), 10);
+        assert_eq!(Rarity::Rare.scale_weight(10, 5), 20);
+        assert_eq!(Rarity::Legendary.scale_weight(u32::MAX, u32::MAX), u32::MAX);
+
+        let mut table = LootTable::new(3, 3);
+
+        for name in ["Crown", "Sceptre"]
+        {
+            table.entries.push(LootEntry { item_type: ItemType::Weapon, name: name.to_string(), weight: u32::MAX, rarity: Rarity::Legendary,
+                min_depth: 0, max_depth: u32::MAX, guaranteed: false, stats: ItemStats::default() });
+        }
+
+        assert_eq!(table.roll_drops(1000, 3).len(), 3);
+    }
+
+    #[test]
+    fn add_loot_to_room_test()
+    {
+        let tables = LootTables::from_text("[default]\nrolls = 0-0\n[boss]\nrolls = 2-2\nWeapon, Crown, 1, Epic, 0-9\n", &[]).unwrap();
+        let mut d = Dungeon::new();
+        d.generate(3, DungeonType::Basement, 40, 40, 8, 8).unwrap();
+
+        d.add_loot(&tables, 1).unwrap();
+        d.add_loot_to_room(1, tables.get_table("boss").unwrap(), 1).unwrap();
+
+        assert!(d.get_room(0).unwrap().items.is_empty());
+        assert_eq!(d.get_room(1).unwrap().items.iter().map(|i| i.desc.as_str()).collect::<Vec<_>>(), ["Crown", "Crown"]);
+        assert!(d.add_loot_to_room(3, tables.get_table("boss").unwrap(), 1).is_err());
+    }
+
+    #[test]
+    fn add_loot_to_room_by_id_test()
+    {
+        let tables = LootTables::from_text("[boss]\nrolls = 1-1\nWeapon, Crown, 1, Epic, 0-9\n", &[]).unwrap();
+        let mut d = Dungeon::from_parts(vec![Room::new(7, 2, 2, 4, 4), Room::new(3, 12, 2, 4, 4)], Vec::new());
+
+        d.add_loot_to_room(3, tables.get_table("boss").unwrap(), 1).unwrap();
+
+        assert!(d.get_room_by_id(7).unwrap().items.is_empty());
+        assert_eq!(d.get_room_by_id(3).unwrap().items.len(), 1);
+        assert!(d.add_loot_to_room(1, tables.get_table("boss").unwrap(), 1).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use rand::{thread_rng, Rng};
+use rand::seq::SliceRandom;
+
+use crate::dungeon::Dungeon;
+use crate::dungeonmap::{DungeonMap, DungeonTile, Region};
+use crate::roles::RoomRole;
+
+/// A monster or a group of the same monsters
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Monster
+{
+    /// An unique id of the monster
+    pub id: usize,
+    /// Name of the monster kind, e.g. "Goblin"
+    pub kind: String,
+    pub level: u32,
+    /// Hit points of every member of the group
+    pub hp: u32,
+    /// Number of monsters in the group
+    pub group_size: u32,
+}
+
+impl Monster
+{
+    pub fn new(mid: usize, k: &str, lvl: u32, h: u32, group: u32) -> Self
+    {
+        Self { id: mid, kind: k.to_string(), level: lvl, hp: h, group_size: group.max(1) }
+    }
+}
+
+/// Position of a monster put on the map
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MonsterPlacement
+{
+    /// Id of the monster. All members of a group have the same id
+    pub monster_id: usize,
+    /// Id of the room the monster stands in
+    pub room_id: usize,
+    pub x: usize,
+    pub y: usize,
+}
+
+/// One kind of monsters that can spawn
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpawnEntry
+{
+    pub kind: String,
+    /// How often the entry is chosen compared to other entries
+    pub weight: u32,
+    /// The lowest dungeon level the monster lives at
+    pub min_level: u32,
+    /// The highest dungeon level the monster lives at
+    pub max_level: u32,
+    /// Hit points gained with every level
+    pub hp_per_level: u32,
+    /// The smallest group of the monsters
+    pub min_group: u32,
+    /// The biggest group of the monsters
+    pub max_group: u32,
+}
+
+/// Weighted list of monsters living in rooms
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpawnTable
+{
+    /// The lowest number of monster groups per room
+    pub min_spawns: u32,
+    /// The highest number of monster groups per room
+    pub max_spawns: u32,
+    pub entries: Vec<SpawnEntry>,
+}
+
+impl Default for SpawnTable
+{
+    fn default() -> Self
+    {
+        let mut table = SpawnTable::new(0, 2);
+
+        for (kind, weight, min_level, max_level, hp_per_level, min_group, max_group) in [
+            ("Rat", 40, 1, 3, 2, 2, 5),
+            ("Goblin", 30, 1, 6, 5, 1, 3),
+            ("Skeleton", 20, 2, 10, 6, 1, 2),
+            ("Orc", 10, 4, 20, 10, 1, 2)]
+        {
+            table.entries.push(SpawnEntry { kind: kind.to_string(), weight, min_level, max_level, hp_per_level, min_group, max_group });
+        }
+
+        table
+    }
+}
+
+impl SpawnTable
+{
+    pub fn new(min_spawns: u32, max_spawns: u32) -> Self
+    {
+        Self { min_spawns, max_spawns: max_spawns.max(min_spawns), entries: Vec::new() }
+    }
+
+    /// Chooses one monster kind for the level. None if no monster lives at the level
+    /// * 'level' - Level of the dungeon
+    pub fn choose(&self, level: u32) -> Option<&SpawnEntry>
+    {
+        let available: Vec<&SpawnEntry> = self.entries.iter().filter(|e| e.min_level <= level && level <= e.max_level).collect();
+
+        available.choose_weighted(&mut thread_rng(), |e| e.weight).ok().copied()
+    }
+
+    /// Creates a group of monsters of the kind
+    /// * 'entry' - The monster kind
+    /// * 'mid' - Id of the new monster
+    /// * 'level' - Level of the dungeon
+    pub fn spawn(entry: &SpawnEntry, mid: usize, level: u32) -> Monster
+    {
+        let group = thread_rng().gen_range(entry.min_group..=entry.max_group.max(entry.min_group));
+
+        Monster::new(mid, &entry.kind, level, entry.hp_per_level * level.max(1), group)
+    }
+}
+
+impl Dungeon
+{
+    /// Gets an id not used by any monster yet
+    pub(crate) fn get_next_monster_id(&self) -> usize
+    {
+        (0..self.get_rooms_number())
+            .flat_map(|r| self.get_room(r).unwrap().monsters.iter())
+            .map(|m| m.id + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Gets a monster by its id
+    /// * 'monster_id' - Id of the monster
+    pub fn get_monster_by_id(&self, monster_id: usize) -> Option<&Monster>
+    {
+        (0..self.get_rooms_number()).flat_map(|r| self.get_room(r).unwrap().monsters.iter()).find(|m| m.id == monster_id)
+    }
+
+    /// Populates chambers with monsters. The start room stays empty, boss rooms get one more group
+    /// * 'table' - Monsters that can spawn
+    /// * 'level' - Level of the dungeon, monsters get this level
+    pub fn add_monsters(&mut self, table: &SpawnTable, level: u32)
+    {
+        let mut rng = thread_rng();
+        let mut monster_id = self.get_next_monster_id();
+        let start = self.get_start_room_id();
+
+        for r in 0..self.get_rooms_number()
+        {
+            let room = self.get_room_mut(r).unwrap();
+
+            if Some(room.id) == start || room.role == RoomRole::Start
+            {
+                continue;
+            }
+
+            let extra = if room.role == RoomRole::Boss { 1 } else { 0 };
+
+            for _ in 0..rng.gen_range(table.min_spawns..=table.max_spawns) + extra
+            {
+                if let Some(entry) = table.choose(level)
+                {
+                    room.monsters.push(SpawnTable::spawn(entry, monster_id, level));
+                    monster_id += 1;
+                }
+            }
+        }
+    }
+}
+
+impl DungeonMap
+{
+    /// Returns true if there is a door next to the cell, diagonals included
+    fn is_near_door(&self, x: usize, y: usize) -> bool
+    {
+        (x.saturating_sub(1)..=x + 1).any(|nx| (y.saturating_sub(1)..=y + 1).any(|ny| self.door_at(nx, ny).is_some()))
+    }
+
+    /// Puts every member of monster groups on a free floor cell of its room, away from doors.
+    /// Monsters that don't fit into the room are left out
+    pub(crate) fn create_monsters(&mut self, dungeon: &Dungeon)
+    {
+        let mut rng = thread_rng();
+        let mut placements = Vec::new();
+
+        for r in 0..dungeon.get_rooms_number()
+        {
+            let room = dungeon.get_room(r).unwrap();
+            let mut free: Vec<(usize, usize)> = Vec::new();
+
+            (room.y as usize..(room.y + room.height) as usize).for_each(|y| {
+                (room.x as usize..(room.x + room.width) as usize).for_each(|x| {
+                    if self.get_tile(x, y) == Some(DungeonTile::TileEmpty as u8) && self.get_region(x, y) == Some(Region::Room(room.id))
+                        && self.item_at(x, y).is_none() && !self.is_near_door(x, y)
+                    {
+                        free.push((x, y));
+                    }
+                });
+            });
+
+            free.shuffle(&mut rng);
+
+            for monster in room.monsters.iter()
+            {
+                (0..monster.group_size).for_each(|_| {
+                    if let Some((x, y)) = free.pop()
+                    {
+                        placements.push(MonsterPlacement { monster_id: monster.id, room_id: room.id, x, y });
+                    }
+                });
+            }
+        }
+
+        self.set_monster_placements(placements);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::dungeon::DungeonType;
+    use crate::room::Room;
+
+    #[test]
+    fn add_monsters_test()
+    {
+        let mut d = Dungeon::new();
+        d.generate(8, DungeonType::Basement, 60, 60, 10, 10).unwrap();
+        d.add_doors().unwrap();
+        d.add_items(true);
+        d.add_monsters(&SpawnTable::new(2, 2), 1);
+
+        assert!((0..d.get_rooms_number()).all(|r| d.get_room(r).unwrap().monsters.is_empty()));
+
+        d.add_monsters(&SpawnTable::default(), 1);
+
+        let start = d.get_room(0).unwrap();
+        assert!(start.monsters.is_empty());
+        assert!((0..d.get_rooms_number()).flat_map(|r| d.get_room(r).unwrap().monsters.iter()).all(|m| m.kind != "Orc"));
+
+        let mut dm = DungeonMap::new(60, 60);
+        dm.create_map(&d);
+
+        for m in dm.get_monster_placements()
+        {
+            assert_eq!(dm.get_tile(m.x, m.y), Some(DungeonTile::TileEmpty as u8));
+            assert_eq!(dm.room_at(m.x, m.y), Some(m.room_id));
+            assert!(dm.item_at(m.x, m.y).is_none());
+            assert!(!dm.is_near_door(m.x, m.y));
+            assert_eq!(dm.monster_at(m.x, m.y), Some(m.monster_id));
+        }
+    }
+
+    #[test]
+    fn add_monsters_fixed_rooms_test()
+    {
+        let mut rooms = vec![Room::new(0, 1, 1, 4, 4), Room::new(1, 7, 1, 4, 4), Room::new(2, 13, 1, 4, 4), Room::new(3, 19, 1, 4, 4)];
+        rooms[2].role = RoomRole::Boss;
+        rooms[3].monsters.push(Monster::new(4, "Bat", 1, 1, 1));
+
+        let mut d = Dungeon::from_parts(rooms, Vec::new());
+
+        let mut table = SpawnTable::new(2, 2);
+        table.entries.push(SpawnEntry { kind: "Rat".to_string(), weight: 1, min_level: 1, max_level: 1, hp_per_level: 3, min_group: 2, max_group: 2 });
+
+        d.add_monsters(&table, 1);
+
+        //The start room stays empty, the boss room gets one more group, monsters already there are kept
+        let counts: Vec<usize> = (0..4).map(|r| d.get_room(r).unwrap().monsters.len()).collect();
+        assert_eq!(counts, [0, 2, 3, 3]);
+
+        let spawned: Vec<&Monster> = (0..4).flat_map(|r| d.get_room(r).unwrap().monsters.iter()).filter(|m| m.kind == "Rat").collect();
+        assert!(spawned.iter().all(|m| m.level == 1 && m.hp == 3 && m.group_size == 2));
+
+        //New ids follow the highest one in use
+        let mut ids: Vec<usize> = spawned.iter().map(|m| m.id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, (5..12).collect::<Vec<usize>>());
+    }
+}
@@ -0,0 +1,301 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::dungeonmap::{DungeonMap, DungeonTile};
+
+/// Directions a walker can move in
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Movement
+{
+    FourWay,    //Only horizontal and vertical steps
+    EightWay,   //Diagonal steps are allowed too
+}
+
+/// Costs and rules of moving on the map. Costs are given for a straight step,
+/// a diagonal step costs 1.4 times more
+#[derive(Clone, PartialEq, Debug)]
+pub struct PathSettings
+{
+    /// Directions the walker can move in
+    pub movement: Movement,
+    /// Cost of stepping on an empty cell or an item
+    pub floor_cost: u32,
+    /// Cost of stepping through an open door
+    pub open_door_cost: u32,
+    /// Cost of stepping through a closed door
+    pub closed_door_cost: u32,
+    /// Cost of stepping through a locked door the walker has a key for
+    pub locked_door_cost: u32,
+    /// Ids of doors the walker has keys for
+    pub keys: Vec<usize>,
+    /// If false a diagonal step next to a wall corner is not allowed
+    pub corner_cutting: bool,
+}
+
+impl Default for PathSettings
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            movement: Movement::FourWay,
+            floor_cost: 10,
+            open_door_cost: 10,
+            closed_door_cost: 20,
+            locked_door_cost: 30,
+            keys: Vec::new(),
+            corner_cutting: false,
+        }
+    }
+}
+
+impl PathSettings
+{
+    /// Gets the lowest possible cost of a straight step
+    fn get_min_cost(&self) -> u32
+    {
+        self.floor_cost
+            .min(self.open_door_cost)
+            .min(self.closed_door_cost)
+            .min(self.locked_door_cost)
+    }
+
+    /// Gets relative positions of neighbour cells
+    pub(crate) fn get_directions(&self) -> &'static [(isize, isize)]
+    {
+        const FOUR_WAY: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+        const EIGHT_WAY: [(isize, isize); 8] = [(0, -1), (1, 0), (0, 1), (-1, 0), (1, -1), (1, 1), (-1, 1), (-1, -1)];
+
+        match self.movement
+        {
+            Movement::FourWay => &FOUR_WAY,
+            Movement::EightWay => &EIGHT_WAY
+        }
+    }
+}
+
+/// Gets cost of a diagonal step from the cost of a straight one
+fn diagonal_cost(cost: u32) -> u32
+{
+    cost * 14 / 10
+}
+
+impl DungeonMap
+{
+    /// Gets cost of a straight step onto given cell. Returns None if the cell can't be entered, e.g. a wall or a blocking prop
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    /// * 'settings' - Movement costs and rules
+    pub fn get_step_cost(&self, x: usize, y: usize, settings: &PathSettings) -> Option<u32>
+    {
+        let tile = self.get_tile(x, y)?;
+
+        if tile == DungeonTile::TileWall as u8 || tile == DungeonTile::TileDummy as u8 || self.is_blocked_by_prop(x, y)
+        {
+            return None;
+        }
+
+        if tile == DungeonTile::TileOpenDoor as u8 || tile == DungeonTile::TileClosedDoor as u8
+        {
+            if let Some(door) = self.door_at(x, y)
+            {
+                if door.locked
+                {
+                    return match settings.keys.contains(&door.id)
+                    {
+                        true => Some(settings.locked_door_cost),
+                        false => None
+                    };
+                }
+            }
+
+            return match tile == DungeonTile::TileOpenDoor as u8
+            {
+                true => Some(settings.open_door_cost),
+                false => Some(settings.closed_door_cost)
+            };
+        }
+
+        Some(settings.floor_cost)
+    }
+
+    /// Gets a neighbour cell and the cost of moving there. Returns None if the move is not possible
+    pub(crate) fn get_move(&self, x: usize, y: usize, dx: isize, dy: isize, settings: &PathSettings) -> Option<(usize, usize, u32)>
+    {
+        let nx = x as isize + dx;
+        let ny = y as isize + dy;
+
+        if nx < 0 || ny < 0
+        {
+            return None;
+        }
+
+        let cost = self.get_step_cost(nx as usize, ny as usize, settings)?;
+
+        if dx != 0 && dy != 0
+        {
+            if !settings.corner_cutting
+                && (self.get_step_cost(nx as usize, y, settings).is_none()
+                    || self.get_step_cost(x, ny as usize, settings).is_none())
+            {
+                return None;
+            }
+
+            return Some((nx as usize, ny as usize, diagonal_cost(cost)));
+        }
+
+        Some((nx as usize, ny as usize, cost))
+    }
+
+    /// Estimates the cost of going from one cell to another. It never overestimates
+    fn estimate_cost(&self, from: (usize, usize), to: (usize, usize), settings: &PathSettings) -> u32
+    {
+        let dx = from.0.abs_diff(to.0) as u32;
+        let dy = from.1.abs_diff(to.1) as u32;
+        let min_cost = settings.get_min_cost();
+
+        match settings.movement
+        {
+            Movement::FourWay => (dx + dy) * min_cost,
+            Movement::EightWay => (dx.max(dy) - dx.min(dy)) * min_cost + dx.min(dy) * diagonal_cost(min_cost)
+        }
+    }
+
+    /// Finds the cheapest path between two cells with A* algorithm.
+    /// Returns all cells of the path including the start and the goal one or None if there's no path
+    /// * 'from' - The start cell
+    /// * 'to' - The goal cell
+    /// * 'settings' - Movement costs and rules
+    pub fn find_path(&self, from: (usize, usize), to: (usize, usize), settings: &PathSettings) -> Option<Vec<(usize, usize)>>
+    {
+        let width = self.get_width();
+        let height = self.get_height();
+
+        if from.0 >= width || from.1 >= height || self.get_step_cost(to.0, to.1, settings).is_none()
+        {
+            return None;
+        }
+
+        let mut costs = vec![vec![u32::MAX; height]; width];
+        let mut came_from: Vec<Vec<Option<(usize, usize)>>> = vec![vec![None; height]; width];
+        let mut open: BinaryHeap<Reverse<(u32, u32, usize, usize)>> = BinaryHeap::new();
+
+        costs[from.0][from.1] = 0;
+        open.push(Reverse((self.estimate_cost(from, to, settings), 0, from.0, from.1)));
+
+        while let Some(Reverse((_, cost, x, y))) = open.pop()
+        {
+            if (x, y) == to
+            {
+                let mut path = vec![to];
+                let mut current = to;
+
+                while let Some(prev) = came_from[current.0][current.1]
+                {
+                    path.push(prev);
+                    current = prev;
+                }
+
+                path.reverse();
+
+                return Some(path);
+            }
+
+            if cost > costs[x][y]
+            {
+                continue;
+            }
+
+            for &(dx, dy) in settings.get_directions()
+            {
+                if let Some((nx, ny, step_cost)) = self.get_move(x, y, dx, dy, settings)
+                {
+                    let new_cost = cost + step_cost;
+
+                    if new_cost < costs[nx][ny]
+                    {
+                        costs[nx][ny] = new_cost;
+                        came_from[nx][ny] = Some((x, y));
+                        open.push(Reverse((new_cost + self.estimate_cost((nx, ny), to, settings), new_cost, nx, ny)));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::ascii::GlyphTable;
+    use crate::door::Door;
+
+    /// Creates a map with two rooms joined by a door at (5, 2)
+    fn create_test_map(door: Door) -> DungeonMap
+    {
+        let mut dm = DungeonMap::new(10, 6);
+
+        (1..9).for_each(|x| {
+            (1..5).for_each(|y| {
+                dm.set_tile(x, y, DungeonTile::TileEmpty as u8);
+            });
+        });
+        (1..5).for_each(|y| {
+            dm.set_tile(5, y, DungeonTile::TileWall as u8);
+        });
+        dm.set_door(5, 2, door);
+
+        dm
+    }
+
+    #[test]
+    fn find_path_test()
+    {
+        let dm = create_test_map(Door { id: 3, locked: false, open: false, secret: false });
+        let mut settings = PathSettings::default();
+
+        let path = dm.find_path((1, 1), (8, 1), &settings).unwrap();
+        assert_eq!(path.first(), Some(&(1, 1)));
+        assert_eq!(path.last(), Some(&(8, 1)));
+        assert!(path.contains(&(5, 2)));
+        assert_eq!(path.len(), 10);
+
+        settings.movement = Movement::EightWay;
+        let path = dm.find_path((1, 1), (8, 1), &settings).unwrap();
+        assert_eq!(path.len(), 8);
+
+        //Walls can't be crossed
+        assert!(dm.find_path((1, 1), (5, 1), &settings).is_none());
+    }
+
+    #[test]
+    fn locked_door_path_test()
+    {
+        let dm = create_test_map(Door { id: 3, locked: true, open: false, secret: false });
+        let mut settings = PathSettings::default();
+
+        assert!(dm.find_path((1, 1), (8, 1), &settings).is_none());
+
+        settings.keys.push(3);
+        assert!(dm.find_path((1, 1), (8, 1), &settings).is_some());
+    }
+
+    #[test]
+    fn corner_cutting_test()
+    {
+        //The only way between the two floor cells is the diagonal step between two walls
+        let dm = DungeonMap::from_ascii("####\n#.##\n##.#\n####\n", &GlyphTable::default()).unwrap();
+        let mut settings = PathSettings { movement: Movement::EightWay, ..PathSettings::default() };
+
+        assert!(dm.get_move(1, 1, 1, 1, &settings).is_none());
+        assert!(dm.find_path((1, 1), (2, 2), &settings).is_none());
+
+        settings.corner_cutting = true;
+
+        assert_eq!(dm.get_move(1, 1, 1, 1, &settings), Some((2, 2, diagonal_cost(settings.floor_cost))));
+        assert_eq!(dm.find_path((1, 1), (2, 2), &settings), Some(vec![(1, 1), (2, 2)]));
+    }
+}
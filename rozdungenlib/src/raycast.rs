@@ -0,0 +1,147 @@
+use crate::dungeonmap::{DungeonMap, DungeonTile};
+
+/// A cell which stopped a line or a ray
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RayHit
+{
+    pub x: usize,
+    pub y: usize,
+    /// The tile standing in the way
+    pub tile: DungeonTile,
+}
+
+/// Gets all cells of a line between two points with Bresenham algorithm, both ends included
+/// * 'from' - The start point
+/// * 'to' - The end point
+pub fn get_line(from: (isize, isize), to: (isize, isize)) -> Vec<(isize, isize)>
+{
+    let mut cells: Vec<(isize, isize)> = Vec::new();
+
+    let dx = (to.0 - from.0).abs();
+    let dy = -(to.1 - from.1).abs();
+    let sx = if from.0 < to.0 { 1 } else { -1 };
+    let sy = if from.1 < to.1 { 1 } else { -1 };
+
+    let mut err = dx + dy;
+    let (mut x, mut y) = from;
+
+    loop
+    {
+        cells.push((x, y));
+
+        if (x, y) == to
+        {
+            break;
+        }
+
+        let e2 = 2 * err;
+
+        if e2 >= dy
+        {
+            err += dy;
+            x += sx;
+        }
+
+        if e2 <= dx
+        {
+            err += dx;
+            y += sy;
+        }
+    }
+
+    cells
+}
+
+impl DungeonMap
+{
+    /// Gets the first cell blocking the sight on the way between two cells or None if the way is clear.
+    /// The end cells never block, so a wall can be seen but not what is behind it
+    /// * 'a' - The start cell
+    /// * 'b' - The end cell
+    pub fn get_line_blocker(&self, a: (usize, usize), b: (usize, usize)) -> Option<RayHit>
+    {
+        let line = get_line((a.0 as isize, a.1 as isize), (b.0 as isize, b.1 as isize));
+
+        for &(x, y) in line.iter().skip(1).take(line.len().saturating_sub(2))
+        {
+            if self.blocks_sight(x, y)
+            {
+                let tile = self.get_tile(x as usize, y as usize)
+                    .and_then(DungeonTile::from_u8)
+                    .unwrap_or(DungeonTile::TileWall);
+
+                return Some(RayHit { x: x as usize, y: y as usize, tile });
+            }
+        }
+
+        None
+    }
+
+    /// Returns true if nothing blocks the sight between two cells
+    /// * 'a' - The start cell
+    /// * 'b' - The end cell
+    pub fn has_line_of_sight(&self, a: (usize, usize), b: (usize, usize)) -> bool
+    {
+        self.get_line_blocker(a, b).is_none()
+    }
+
+    /// Casts a ray and gets the first cell blocking it. Returns None if the ray goes
+    /// the whole distance or leaves the map without hitting anything
+    /// * 'from' - The cell the ray starts from
+    /// * 'dir' - Direction of the ray, it doesn't need to be normalized
+    /// * 'max' - The longest distance the ray goes
+    pub fn raycast(&self, from: (usize, usize), dir: (f32, f32), max: usize) -> Option<RayHit>
+    {
+        let length = (dir.0 * dir.0 + dir.1 * dir.1).sqrt();
+
+        if length == 0.0
+        {
+            return None;
+        }
+
+        let to = ((from.0 as f32 + dir.0 / length * max as f32).round() as isize,
+            (from.1 as f32 + dir.1 / length * max as f32).round() as isize);
+
+        for (x, y) in get_line((from.0 as isize, from.1 as isize), to).into_iter().skip(1)
+        {
+            if x < 0 || y < 0 || self.get_tile(x as usize, y as usize).is_none()
+            {
+                return None;
+            }
+
+            if self.blocks_sight(x, y)
+            {
+                let tile = DungeonTile::from_u8(self.get_tile(x as usize, y as usize).unwrap())
+                    .unwrap_or(DungeonTile::TileWall);
+
+                return Some(RayHit { x: x as usize, y: y as usize, tile });
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn line_of_sight_test()
+    {
+        let mut dm = DungeonMap::new(10, 5);
+        (1..9).for_each(|x| {
+            (1..4).for_each(|y| dm.set_tile(x, y, DungeonTile::TileEmpty as u8));
+        });
+        dm.set_tile(5, 2, DungeonTile::TileClosedDoor as u8);
+
+        assert!(dm.has_line_of_sight((1, 1), (8, 1)));
+        assert!(dm.has_line_of_sight((1, 2), (5, 2)));
+        assert_eq!(dm.get_line_blocker((1, 2), (8, 2)), Some(RayHit { x: 5, y: 2, tile: DungeonTile::TileClosedDoor }));
+
+        assert_eq!(dm.raycast((1, 2), (1.0, 0.0), 10), Some(RayHit { x: 5, y: 2, tile: DungeonTile::TileClosedDoor }));
+        assert_eq!(dm.raycast((1, 1), (0.0, -1.0), 10), Some(RayHit { x: 1, y: 0, tile: DungeonTile::TileWall }));
+        assert_eq!(dm.raycast((1, 1), (1.0, 0.0), 3), None);
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::decoration::PropKind;
+use crate::dungeonmap::{DungeonMap, DungeonTile, Region};
+
+/// Colour of the space outside of rooms and corridors, the same as in the example renderer
+const VOID_COLOUR: [u8; 4] = [26, 51, 77, 255];
+/// Colour of floor cells
+const FLOOR_COLOUR: [u8; 4] = [60, 60, 60, 255];
+/// Colour of monster markers
+const MONSTER_COLOUR: [u8; 4] = [200, 40, 40, 255];
+/// Colour of trap markers
+const TRAP_COLOUR: [u8; 4] = [170, 60, 200, 255];
+
+/// Gets the colour of the prop marker
+fn get_prop_colour(prop: PropKind) -> [u8; 4]
+{
+    match prop
+    {
+        PropKind::Pillar => [110, 110, 120, 255],
+        PropKind::Table => [150, 100, 50, 255],
+        PropKind::Bookshelf => [110, 70, 40, 255],
+        PropKind::Altar => [230, 230, 230, 255],
+        PropKind::Rubble => [100, 90, 80, 255],
+        PropKind::Bones => [210, 200, 170, 255],
+        PropKind::Carpet => [130, 30, 50, 255]
+    }
+}
+
+/// Names of tile images looked for in a tileset directory
+const TILE_FILES: [(DungeonTile, &str); 5] = [
+    (DungeonTile::TileWall, "wall.png"),
+    (DungeonTile::TileOpenDoor, "door_open.png"),
+    (DungeonTile::TileClosedDoor, "door_closed.png"),
+    (DungeonTile::TileChest, "chest.png"),
+    (DungeonTile::TileKey, "key.png"),
+];
+
+/// RGBA image of a single tile, already scaled to the tile size
+#[derive(Clone)]
+struct TileImage
+{
+    pixels: Vec<u8>,
+}
+
+/// Images of map tiles. Tiles with no image are drawn as plain squares
+#[derive(Clone)]
+pub struct Tileset
+{
+    tile_size: u32,
+    images: HashMap<u8, TileImage>,
+}
+
+/// Gets the colour a tile with no image is drawn with
+fn get_fallback_colour(tile: DungeonTile) -> [u8; 4]
+{
+    match tile
+    {
+        DungeonTile::TileEmpty => FLOOR_COLOUR,
+        DungeonTile::TileWall | DungeonTile::TileDummy => [140, 140, 140, 255],
+        DungeonTile::TileClosedDoor => [140, 80, 30, 255],
+        DungeonTile::TileOpenDoor => [200, 150, 90, 255],
+        DungeonTile::TileChest => [220, 180, 40, 255],
+        DungeonTile::TileKey => [240, 230, 120, 255],
+        DungeonTile::TileStairsUp => [120, 200, 120, 255],
+        DungeonTile::TileStairsDown => [60, 120, 200, 255]
+    }
+}
+
+/// Decodes PNG data into RGBA pixels
+fn decode_png(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String>
+{
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    let bytes = &buf[..info.buffer_size()];
+
+    let pixels: Vec<u8> = match info.color_type
+    {
+        png::ColorType::Rgba => bytes.to_vec(),
+        png::ColorType::Rgb => bytes.chunks(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => bytes.chunks(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => bytes.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+        png::ColorType::Indexed => return Err("Indexed PNG images are not supported!".to_string())
+    };
+
+    Ok((info.width, info.height, pixels))
+}
+
+impl Tileset
+{
+    /// Creates a tileset with no images. All tiles are drawn as plain squares
+    /// * 'tile_size' - Width and height of a tile in pixels
+    pub fn new(tile_size: u32) -> Self
+    {
+        Self { tile_size: tile_size.max(1), images: HashMap::new() }
+    }
+
+    /// Loads tile images from a directory laid out like resources/images:
+    /// wall.png, door_open.png, door_closed.png, chest.png and key.png. Missing files are skipped
+    /// * 'dir' - The directory with images
+    /// * 'tile_size' - Width and height of a tile in pixels
+    pub fn from_dir(dir: &Path, tile_size: u32) -> Result<Self, String>
+    {
+        let mut tileset = Self::new(tile_size);
+
+        for (tile, file_name) in TILE_FILES
+        {
+            let path = dir.join(file_name);
+
+            if path.exists()
+            {
+                let data = fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                tileset.set_tile_image(tile, &data).map_err(|e| format!("{}: {}", path.display(), e))?;
+            }
+        }
+
+        Ok(tileset)
+    }
+
+    /// Gets width and height of a tile in pixels
+    pub fn get_tile_size(&self) -> u32
+    {
+        self.tile_size
+    }
+
+    /// Sets an image of the tile. Images of a different size are scaled to the tile size
+    /// * 'tile' - The tile
+    /// * 'png_data' - Content of a PNG file
+    pub fn set_tile_image(&mut self, tile: DungeonTile, png_data: &[u8]) -> Result<(), String>
+    {
+        let (width, height, pixels) = decode_png(png_data)?;
+        let size = self.tile_size;
+        let mut scaled = vec![0; (size * size * 4) as usize];
+
+        (0..size).for_each(|y| {
+            (0..size).for_each(|x| {
+                let src = ((y * height / size * width + x * width / size) * 4) as usize;
+                let dst = ((y * size + x) * 4) as usize;
+
+                scaled[dst..dst + 4].copy_from_slice(&pixels[src..src + 4]);
+            });
+        });
+
+        self.images.insert(tile as u8, TileImage { pixels: scaled });
+
+        Ok(())
+    }
+
+    /// Draws one tile into the image, blending it with the background. No tile means only the background is drawn
+    fn draw_tile(&self, image: &mut [u8], image_width: usize, tile_x: usize, tile_y: usize, tile: Option<u8>, background: [u8; 4])
+    {
+        let size = self.tile_size as usize;
+        let fallback = tile.and_then(DungeonTile::from_u8).map(get_fallback_colour).unwrap_or(background);
+        let tile_image = tile.and_then(|t| self.images.get(&t));
+
+        (0..size).for_each(|y| {
+            (0..size).for_each(|x| {
+                let dst = ((tile_y * size + y) * image_width + tile_x * size + x) * 4;
+
+                let pixel = match tile_image
+                {
+                    Some(img) =>
+                    {
+                        let src = (y * size + x) * 4;
+                        let p = &img.pixels[src..src + 4];
+                        let a = p[3] as u32;
+
+                        [0, 1, 2].map(|i| ((p[i] as u32 * a + background[i] as u32 * (255 - a)) / 255) as u8)
+                    },
+                    None => [fallback[0], fallback[1], fallback[2]]
+                };
+
+                image[dst..dst + 3].copy_from_slice(&pixel);
+                image[dst + 3] = 255;
+            });
+        });
+    }
+
+    /// Draws a square marker in the middle of the tile, half as big as the tile
+    fn draw_marker(&self, image: &mut [u8], image_width: usize, tile_x: usize, tile_y: usize, colour: [u8; 4])
+    {
+        let size = self.tile_size as usize;
+        let margin = size / 4;
+
+        (margin..size - margin).for_each(|y| {
+            (margin..size - margin).for_each(|x| {
+                let dst = ((tile_y * size + y) * image_width + tile_x * size + x) * 4;
+
+                image[dst..dst + 4].copy_from_slice(&colour);
+            });
+        });
+    }
+}
+
+impl DungeonMap
+{
+    /// Renders the map into PNG data without opening any window. Props, monsters and traps, hidden ones too,
+    /// are drawn as markers over their tiles
+    /// * 'tileset' - Images of tiles
+    pub fn render_png(&self, tileset: &Tileset) -> Result<Vec<u8>, String>
+    {
+        let size = tileset.get_tile_size() as usize;
+        let too_big = || "The map is too big for a PNG image!".to_string();
+        let width = self.get_width().checked_mul(size).ok_or_else(too_big)?;
+        let height = self.get_height().checked_mul(size).ok_or_else(too_big)?;
+
+        if width == 0 || height == 0
+        {
+            return Err("The map is empty!".to_string());
+        }
+
+        let png_width = u32::try_from(width).map_err(|_| too_big())?;
+        let png_height = u32::try_from(height).map_err(|_| too_big())?;
+        let buffer_size = width.checked_mul(height).and_then(|s| s.checked_mul(4)).ok_or_else(too_big)?;
+
+        let mut image = vec![0; buffer_size];
+
+        (0..self.get_height()).for_each(|y| {
+            (0..self.get_width()).for_each(|x| {
+                let tile = self.get_tile(x, y).unwrap();
+                let void = tile == DungeonTile::TileEmpty as u8 && self.get_region(x, y) == Some(Region::Void);
+
+                let (tile, background) = match void
+                {
+                    true => (None, VOID_COLOUR),
+                    false => (Some(tile), FLOOR_COLOUR)
+                };
+
+                tileset.draw_tile(&mut image, width, x, y, tile, background);
+            });
+        });
+
+        (0..self.get_height()).for_each(|y| {
+            (0..self.get_width()).for_each(|x| {
+                if let Some(prop) = self.prop_at(x, y)
+                {
+                    tileset.draw_marker(&mut image, width, x, y, get_prop_colour(prop));
+                }
+            });
+        });
+
+        for trap in self.get_traps()
+        {
+            tileset.draw_marker(&mut image, width, trap.x, trap.y, TRAP_COLOUR);
+        }
+
+        for monster in self.get_monster_placements()
+        {
+            tileset.draw_marker(&mut image, width, monster.x, monster.y, MONSTER_COLOUR);
+        }
+
+        let mut data: Vec<u8> = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, png_width, png_height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer.write_image_data(&image).map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| e.to_string())?;
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::ascii::GlyphTable;
+
+    #[test]
+    fn render_png_test()
+    {
+        let map = DungeonMap::from_ascii("#####\n#.$+#\n#####\n", &GlyphTable::default()).unwrap();
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../resources/images");
+        let tileset = Tileset::from_dir(&dir, 16).unwrap();
+
+        let data = map.render_png(&tileset).unwrap();
+        let (width, height, _) = decode_png(&data).unwrap();
+
+        assert_eq!((width, height), (80, 48));
+    }
+
+    #[test]
+    fn render_markers_test()
+    {
+        let glyphs = GlyphTable::default();
+        let map = DungeonMap::from_ascii(&format!("######\n#.M^{}#\n######\n", glyphs.altar), &glyphs).unwrap();
+        let data = map.render_png(&Tileset::new(8)).unwrap();
+        let (width, _, pixels) = decode_png(&data).unwrap();
+        let pixel = |x: u32, y: u32| { let i = ((y * width + x) * 4) as usize; [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]] };
+
+        //Middles of the floor, monster, trap and prop cells
+        assert_eq!(pixel(12, 12), FLOOR_COLOUR);
+        assert_eq!(pixel(20, 12), MONSTER_COLOUR);
+        assert_eq!(pixel(28, 12), TRAP_COLOUR);
+        assert_eq!(pixel(36, 12), get_prop_colour(PropKind::Altar));
+
+        //Markers leave the edges of the cells alone
+        assert_eq!(pixel(16, 8), FLOOR_COLOUR);
+    }
+}
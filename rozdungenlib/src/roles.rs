@@ -0,0 +1,177 @@
+use crate::dungeon::Dungeon;
+
+/// Rooms connected with at least this many other rooms are hubs
+pub const HUB_MIN_CORRIDORS: usize = 3;
+
+/// What a room is used for. Item, monster and decoration passes tailor rooms by their roles
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RoomRole
+{
+    #[default]
+    Ordinary,       //A room with no special purpose
+    Start,          //The room the player starts in
+    Exit,           //The room with the way out of the dungeon
+    Boss,           //The biggest room guarding the exit
+    TreasureVault,  //A dead end with richer loot
+    Hub,            //A room many corridors meet in
+    Secret,         //A hidden room reachable only through secret doors
+}
+
+impl RoomRole
+{
+    /// Gets the name of the role. Loot tables with this name are used for rooms of the role
+    pub fn get_name(&self) -> &'static str
+    {
+        match self
+        {
+            RoomRole::Ordinary => "ordinary",
+            RoomRole::Start => "start",
+            RoomRole::Exit => "exit",
+            RoomRole::Boss => "boss",
+            RoomRole::TreasureVault => "treasure_vault",
+            RoomRole::Hub => "hub",
+            RoomRole::Secret => "secret",
+        }
+    }
+
+    /// Gets the threat of rooms of the role in percents of the threat of an ordinary room
+    pub fn get_threat_percent(&self) -> u32
+    {
+        match self
+        {
+            RoomRole::Start => 0,
+            RoomRole::Boss => 200,
+            RoomRole::TreasureVault => 150,
+            RoomRole::Secret => 50,
+            _ => 100
+        }
+    }
+}
+
+impl Dungeon
+{
+    /// Gets the number of different rooms the room is connected with. Many corridors to the same room count once
+    fn get_room_degree(&self, room_id: usize) -> usize
+    {
+        let mut neighbours: Vec<usize> = self.get_room_neighbours(room_id).into_iter().map(|(_, id)| id).collect();
+        neighbours.sort();
+        neighbours.dedup();
+
+        neighbours.len()
+    }
+
+    /// Gets the room with the way out: the exit room or the room farthest from the start if there is no exit
+    fn get_exit_room_id(&self, start: usize) -> usize
+    {
+        match self.get_exit()
+        {
+            Some(exit) => exit.room_id,
+            None => self.get_room_graph_distances(start).into_iter()
+                .max_by_key(|&(id, d)| (d, self.get_room_by_id(id).map(|r| r.width as u32 * r.height as u32)))
+                .map(|(id, _)| id)
+                .unwrap_or(start)
+        }
+    }
+
+    /// Classifies rooms using the corridor graph: the start and exit rooms come from the stairs,
+    /// the biggest neighbour of the exit becomes the boss room, dead ends become treasure vaults
+    /// and rooms connected with many other rooms become hubs. Secret rooms keep their role
+    pub fn assign_room_roles(&mut self) -> Result<(), String>
+    {
+        let start = self.get_start_room_id().ok_or("There are no rooms in the dungeon!")?;
+        let exit = self.get_exit_room_id(start);
+
+        let boss = self.get_room_neighbours(exit).into_iter()
+            .map(|(_, id)| id)
+            .filter(|&id| id != start && id != exit && self.get_room_by_id(id).map(|r| r.role) != Some(RoomRole::Secret))
+            .max_by_key(|&id| self.get_room_by_id(id).map(|r| r.width as u32 * r.height as u32));
+
+        for r in 0..self.get_rooms_number()
+        {
+            let id = self.get_room(r).unwrap().id;
+            let degree = self.get_room_degree(id);
+            let room = self.get_room_mut(r).unwrap();
+
+            room.role = if id == start { RoomRole::Start }
+                else if id == exit { RoomRole::Exit }
+                else if room.role == RoomRole::Secret { RoomRole::Secret }
+                else if Some(id) == boss { RoomRole::Boss }
+                else if degree == 1 { RoomRole::TreasureVault }
+                else if degree >= HUB_MIN_CORRIDORS { RoomRole::Hub }
+                else { RoomRole::Ordinary };
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::corridor::Corridor;
+    use crate::dungeon::DungeonType;
+    use crate::dungeonmap::DungeonMap;
+    use crate::room::Room;
+    use crate::stairs::Stairs;
+
+    #[test]
+    fn assign_room_roles_test()
+    {
+        let mut d = Dungeon::new();
+        d.generate(10, DungeonType::Basement, 80, 80, 8, 8).unwrap();
+        d.add_doors().unwrap();
+
+        let mut dm = DungeonMap::new(80, 80);
+        dm.create_map(&d);
+        d.add_stairs(&dm, false).unwrap();
+        d.assign_room_roles().unwrap();
+
+        let entrance = d.get_entrance().unwrap().room_id;
+        let exit = d.get_exit().unwrap().room_id;
+        let rooms: Vec<_> = (0..d.get_rooms_number()).map(|r| d.get_room(r).unwrap()).collect();
+
+        assert_eq!(rooms.iter().filter(|r| r.role == RoomRole::Start).count(), 1);
+        assert_eq!(rooms.iter().filter(|r| r.role == RoomRole::Exit).count(), 1);
+        assert!(rooms.iter().filter(|r| r.role == RoomRole::Boss).count() <= 1);
+
+        for room in rooms
+        {
+            let neighbours = d.get_room_neighbours(room.id);
+            let degree = d.get_room_degree(room.id);
+
+            match room.role
+            {
+                RoomRole::Start => assert_eq!(room.id, entrance),
+                RoomRole::Exit => assert_eq!(room.id, exit),
+                RoomRole::Boss => assert!(neighbours.iter().any(|&(_, id)| id == exit)),
+                RoomRole::TreasureVault => assert_eq!(degree, 1),
+                RoomRole::Hub => assert!(degree >= HUB_MIN_CORRIDORS),
+                RoomRole::Ordinary => assert!(degree != 1 && degree < HUB_MIN_CORRIDORS),
+                RoomRole::Secret => assert!(neighbours.iter().all(|&(c, _)| d.is_corridor_secret(c)))
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_graph_roles_test()
+    {
+        //0 - 1 - 3 - 4 - 5 with a dead end 2 on room 1 and a dead end 6 on room 4.
+        //Rooms 1 and 3 and rooms 4 and 6 are joined by two corridors each
+        let rooms = vec![Room::new(0, 0, 0, 4, 4), Room::new(1, 10, 0, 4, 4), Room::new(2, 10, 10, 4, 4), Room::new(3, 20, 0, 4, 4),
+            Room::new(4, 30, 0, 8, 8), Room::new(5, 40, 0, 4, 4), Room::new(6, 30, 10, 4, 4)];
+        let corridors = [(0, 1), (1, 2), (1, 3), (3, 1), (3, 4), (4, 5), (4, 6), (6, 4)].iter().enumerate()
+            .map(|(id, &(from, to))| Corridor::new(id, from, to, None, None))
+            .collect();
+
+        let mut d = Dungeon::from_parts(rooms, corridors);
+        d.set_stairs(Some(Stairs { room_id: 0, x: 1, y: 1 }), Some(Stairs { room_id: 5, x: 41, y: 1 }));
+        d.assign_room_roles().unwrap();
+
+        let roles: Vec<RoomRole> = (0..d.get_rooms_number()).map(|r| d.get_room(r).unwrap().role).collect();
+
+        assert_eq!(roles, [RoomRole::Start, RoomRole::Hub, RoomRole::TreasureVault, RoomRole::Ordinary,
+            RoomRole::Boss, RoomRole::Exit, RoomRole::TreasureVault]);
+    }
+}
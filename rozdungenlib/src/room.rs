@@ -1,7 +1,11 @@
 use crate::item::Item;
+use crate::light::LightSource;
+use crate::monster::Monster;
+use crate::roles::RoomRole;
 
 
 #[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Room
 {
     pub id: usize,
@@ -10,6 +14,16 @@ pub struct Room
     pub width: u16,
     pub height: u16,
     pub items: Vec<Item>,
+    /// Lights of the room. A room with no lights is dark
+    pub lights: Vec<LightSource>,
+    /// Monsters living in the room
+    pub monsters: Vec<Monster>,
+    /// Danger of the room planned by the difficulty planner
+    pub threat: u32,
+    /// Part of the threat left for traps
+    pub trap_budget: u32,
+    /// What the room is used for in the dungeon
+    pub role: RoomRole,
 }
 
 impl Room
@@ -17,6 +31,7 @@ impl Room
     pub fn new(rid: usize, xp: u16, yp: u16, w: u16, h: u16) -> Self
     {
         Self { id: rid, x: xp, y: yp, width: w, height: h,
-            items: Vec::<Item>::new() }
+            items: Vec::<Item>::new(), lights: Vec::<LightSource>::new(), monsters: Vec::<Monster>::new(),
+            threat: 0, trap_budget: 0, role: RoomRole::Ordinary }
     }
 }
\ No newline at end of file
@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+
+use crate::corridor::Corridor;
+use crate::dungeon::Dungeon;
+
+/// Returns true if every door of the corridor is unlocked or opened with one of the keys
+fn is_corridor_passable(corridor: &Corridor, keys: &[usize], extra_locked: Option<usize>) -> bool
+{
+    [corridor.from_room_door, corridor.to_room_door].iter().flatten()
+        .all(|d| !(d.locked || Some(d.id) == extra_locked) || keys.contains(&d.id))
+}
+
+impl Dungeon
+{
+    /// Gets id of the room the player starts in: the entrance room or the first room if there is no entrance
+    pub fn get_start_room_id(&self) -> Option<usize>
+    {
+        match self.get_entrance()
+        {
+            Some(entrance) => Some(entrance.room_id),
+            None => self.get_room(0).map(|r| r.id)
+        }
+    }
+
+    /// Gets rooms connected with the room by corridors as (corridor id, room id) pairs
+    /// * 'room_id' - Id of the room
+    pub fn get_room_neighbours(&self, room_id: usize) -> Vec<(usize, usize)>
+    {
+        (0..self.get_corridors_number())
+            .map(|c| self.get_corridor(c).unwrap())
+            .filter_map(|c| match (c.from_room_id == room_id, c.to_room_id == room_id)
+            {
+                (true, _) => Some((c.id, c.to_room_id)),
+                (_, true) => Some((c.id, c.from_room_id)),
+                _ => None
+            })
+            .collect()
+    }
+
+    /// Gets how many corridors lead from the room to every room connected with it as (room id, distance) pairs.
+    /// Locked doors are ignored
+    /// * 'room_id' - Id of the room
+    pub fn get_room_graph_distances(&self, room_id: usize) -> Vec<(usize, usize)>
+    {
+        let mut distances = vec![(room_id, 0)];
+        let mut queue = VecDeque::from([(room_id, 0)]);
+
+        while let Some((id, distance)) = queue.pop_front()
+        {
+            for (_, next) in self.get_room_neighbours(id)
+            {
+                if !distances.iter().any(|&(r, _)| r == next)
+                {
+                    distances.push((next, distance + 1));
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Gets ids of rooms reachable from the room without passing locked doors the keys don't open
+    /// * 'room_id' - Id of the room
+    /// * 'keys' - Ids of doors the player has keys for
+    pub fn get_reachable_rooms(&self, room_id: usize, keys: &[usize]) -> Vec<usize>
+    {
+        self.find_reachable_rooms(room_id, keys, None)
+    }
+
+    /// Finds reachable rooms like get_reachable_rooms. The extra door is treated as locked
+    pub(crate) fn find_reachable_rooms(&self, room_id: usize, keys: &[usize], extra_locked: Option<usize>) -> Vec<usize>
+    {
+        let mut reached = vec![room_id];
+        let mut queue = VecDeque::from([room_id]);
+
+        while let Some(id) = queue.pop_front()
+        {
+            for (corridor_id, next) in self.get_room_neighbours(id)
+            {
+                let corridor = self.get_corridor(corridor_id).unwrap();
+
+                if !reached.contains(&next) && is_corridor_passable(corridor, keys, extra_locked)
+                {
+                    reached.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        reached
+    }
+}
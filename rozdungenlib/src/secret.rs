@@ -0,0 +1,357 @@
+use std::collections::VecDeque;
+
+use rand::thread_rng;
+use rand::seq::SliceRandom;
+
+use crate::connectivity::DoorPassage;
+use crate::dijkstra::DijkstraMap;
+use crate::door::Door;
+use crate::dungeon::Dungeon;
+use crate::dungeonmap::{DungeonMap, Feature};
+use crate::item::ItemType;
+use crate::pathfinding::PathSettings;
+use crate::roles::RoomRole;
+
+impl Dungeon
+{
+    /// Gets an id not used by any door yet
+    pub(crate) fn get_next_door_id(&self) -> usize
+    {
+        (0..self.get_corridors_number())
+            .map(|c| self.get_corridor(c).unwrap())
+            .flat_map(|c| [c.from_room_door, c.to_room_door].into_iter().flatten())
+            .map(|d| d.id + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns true if the corridor has a secret door
+    pub(crate) fn is_corridor_secret(&self, corridor_id: usize) -> bool
+    {
+        let corridor = self.get_corridor(corridor_id).unwrap();
+
+        [corridor.from_room_door, corridor.to_room_door].iter().flatten().any(|d| d.secret)
+    }
+
+    /// Returns true if rooms on both ends of the corridor stay connected without it and without secret corridors
+    fn is_loop_corridor(&self, corridor_id: usize) -> bool
+    {
+        let corridor = self.get_corridor(corridor_id).unwrap();
+        let mut reached = vec![corridor.from_room_id];
+        let mut queue = VecDeque::from([corridor.from_room_id]);
+
+        while let Some(id) = queue.pop_front()
+        {
+            for (c, next) in self.get_room_neighbours(id)
+            {
+                if c != corridor_id && !reached.contains(&next) && !self.is_corridor_secret(c)
+                {
+                    reached.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        reached.contains(&corridor.to_room_id)
+    }
+
+    /// Makes all doors of the corridor secret
+    fn hide_doors(&mut self, corridor_id: usize)
+    {
+        let corridor = self.get_corridor_mut(corridor_id).unwrap();
+
+        [&mut corridor.from_room_door, &mut corridor.to_room_door].into_iter().flatten().for_each(|d| {
+            d.secret = true;
+            d.open = false;
+        });
+    }
+
+    /// Gets a copy of the map with doors of the dungeon put on it again and doors of the corridor made secret
+    fn get_map_with_hidden_corridor(&self, map: &DungeonMap, corridor_id: Option<usize>) -> DungeonMap
+    {
+        let mut map = map.clone();
+        map.update_doors(self);
+
+        let ids: Vec<usize> = match corridor_id.and_then(|c| self.get_corridor(c))
+        {
+            Some(c) => [c.from_room_door, c.to_room_door].iter().flatten().map(|d| d.id).collect(),
+            None => Vec::new()
+        };
+
+        (0..map.get_height()).for_each(|y| {
+            (0..map.get_width()).for_each(|x| {
+                if let Some(door) = map.door_at(x, y).filter(|d| ids.contains(&d.id))
+                {
+                    map.set_door(x, y, Door { secret: true, open: false, ..door });
+                }
+            });
+        });
+
+        map
+    }
+
+    /// Gets rooms which can't be reached on the map from the start cell without discovering secret doors.
+    /// None if there is no start cell
+    fn get_map_unreachable_rooms(&self, map: &DungeonMap) -> Option<Vec<usize>>
+    {
+        let (x, y) = self.get_start_cell(map)?;
+
+        map.validate_connectivity(x, y, DoorPassage::All).ok().map(|r| r.unreachable_rooms)
+    }
+
+    /// Gets the walking distance on the map between two rooms, passing every door that is not secret
+    fn get_map_room_distance(&self, map: &DungeonMap, from: usize, to: usize) -> Option<i32>
+    {
+        let settings = PathSettings { keys: (0..self.get_next_door_id()).collect(), ..PathSettings::default() };
+        let cell = self.get_room_cell(map, from)?;
+
+        DijkstraMap::new(map, &[cell], &settings).get_room_distance(map, to)
+    }
+
+    /// Hides corridors closing loops behind secret doors, so the player can find shortcuts.
+    /// A corridor is hidden only if its secret door really makes the way between its rooms longer on the map
+    /// and every room can still be reached. Returns the number of hidden corridors which can be lower than requested
+    /// if there are not enough loops. Must be called after add_doors with the map created from the dungeon;
+    /// DungeonMap::populate puts the secret doors on the map
+    /// * 'map' - The map created from the dungeon
+    /// * 'count' - Number of corridors to hide
+    pub fn add_secret_doors(&mut self, map: &DungeonMap, count: usize) -> Result<usize, String>
+    {
+        if self.get_corridors_number() == 0
+        {
+            return Err("There are no corridors in the dungeon!".to_string());
+        }
+
+        let mut corridors: Vec<usize> = (0..self.get_corridors_number()).collect();
+        let mut hidden = 0;
+
+        corridors.shuffle(&mut thread_rng());
+
+        for c in corridors
+        {
+            if hidden == count
+            {
+                break;
+            }
+
+            let corridor = self.get_corridor(c).unwrap();
+            let doors: Vec<Door> = [corridor.from_room_door, corridor.to_room_door].into_iter().flatten().collect();
+            let (from, to) = (corridor.from_room_id, corridor.to_room_id);
+
+            if doors.is_empty() || doors.iter().any(|d| d.locked) || self.is_corridor_secret(c) || !self.is_loop_corridor(c)
+            {
+                continue;
+            }
+
+            let before = self.get_map_with_hidden_corridor(map, None);
+            let after = self.get_map_with_hidden_corridor(map, Some(c));
+
+            let longer = match (self.get_map_room_distance(&before, from, to), self.get_map_room_distance(&after, from, to))
+            {
+                (Some(b), Some(a)) => a > b,
+                _ => false
+            };
+
+            let connected = match (self.get_map_unreachable_rooms(&before), self.get_map_unreachable_rooms(&after))
+            {
+                (Some(b), Some(a)) => a.iter().all(|id| b.contains(id)),
+                _ => false
+            };
+
+            if longer && connected
+            {
+                self.hide_doors(c);
+                hidden += 1;
+            }
+        }
+
+        Ok(hidden)
+    }
+
+    /// Turns dead end rooms into hidden side rooms reachable only through a secret door.
+    /// A room is hidden only if its secret door is on the map and cuts off the room and nothing else.
+    /// Hidden rooms get the Secret role. Rooms with stairs or keys are never hidden.
+    /// Returns the number of hidden rooms which can be lower than requested. Must be called after add_stairs;
+    /// DungeonMap::populate puts the secret doors on the map
+    /// * 'map' - The map created from the dungeon
+    /// * 'count' - Number of rooms to hide
+    pub fn add_hidden_rooms(&mut self, map: &DungeonMap, count: usize) -> Result<usize, String>
+    {
+        let start = self.get_start_room_id().ok_or("There are no rooms in the dungeon!")?;
+        let mut rooms: Vec<usize> = (0..self.get_rooms_number()).collect();
+        let mut hidden = 0;
+
+        rooms.shuffle(&mut thread_rng());
+
+        for r in rooms
+        {
+            let room = self.get_room(r).unwrap();
+            let room_id = room.id;
+            let neighbours = self.get_room_neighbours(room.id);
+            let stairs = [self.get_entrance(), self.get_exit()].iter().flatten().any(|s| s.room_id == room.id);
+            let keys = room.items.iter().any(|i| matches!(i.item_type, ItemType::Key(_)));
+
+            if hidden == count
+            {
+                break;
+            }
+
+            if room.id == start || stairs || keys || neighbours.len() != 1
+            {
+                continue;
+            }
+
+            let corridor_id = neighbours[0].0;
+            let corridor = self.get_corridor(corridor_id).unwrap();
+
+            if [corridor.from_room_door, corridor.to_room_door].iter().flatten().any(|d| d.locked)
+            {
+                continue;
+            }
+
+            let before = self.get_map_unreachable_rooms(&self.get_map_with_hidden_corridor(map, None));
+            let after = self.get_map_unreachable_rooms(&self.get_map_with_hidden_corridor(map, Some(corridor_id)));
+
+            let cut_off = match (before, after)
+            {
+                (Some(b), Some(a)) => a.iter().filter(|id| !b.contains(id)).eq([room_id].iter()),
+                _ => false
+            };
+
+            if cut_off
+            {
+                self.hide_doors(corridor_id);
+                self.get_room_mut(r).unwrap().role = RoomRole::Secret;
+                hidden += 1;
+            }
+        }
+
+        Ok(hidden)
+    }
+}
+
+impl DungeonMap
+{
+    /// Discovers a secret door at given position, so it becomes an ordinary closed door.
+    /// Returns the discovered door or None if there is no secret door there
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn discover_at(&mut self, x: usize, y: usize) -> Option<Door>
+    {
+        if !matches!(self.get_feature(x, y), Some(Feature::SecretDoor(_)))
+        {
+            return None;
+        }
+
+        let door = Door { secret: false, ..self.door_at(x, y)? };
+        self.set_door(x, y, door);
+
+        Some(door)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::corridor::Corridor;
+    use crate::dungeon::DungeonType;
+    use crate::dungeonmap::DungeonTile;
+    use crate::room::Room;
+    use crate::stairs::Stairs;
+
+    /// Discovers every secret door on the map, checking how it looks before and after
+    fn discover_all(dm: &mut DungeonMap)
+    {
+        (0..dm.get_height()).for_each(|y| {
+            (0..dm.get_width()).for_each(|x| {
+                match dm.door_at(x, y)
+                {
+                    Some(door) if door.secret =>
+                    {
+                        assert_eq!(dm.get_tile(x, y), Some(DungeonTile::TileWall as u8));
+                        assert_eq!(dm.get_feature(x, y), Some(Feature::SecretDoor(door.id)));
+
+                        let found = dm.discover_at(x, y).unwrap();
+
+                        assert!(!found.secret && !found.open);
+                        assert_eq!(dm.get_tile(x, y), Some(DungeonTile::TileClosedDoor as u8));
+                        assert_eq!(dm.get_feature(x, y), Some(Feature::None));
+                    },
+                    _ => assert!(dm.discover_at(x, y).is_none())
+                }
+            });
+        });
+    }
+
+    #[test]
+    fn secret_doors_test()
+    {
+        let mut d = Dungeon::new();
+        d.generate(12, DungeonType::Basement, 80, 80, 8, 8).unwrap();
+        d.add_doors().unwrap();
+
+        let mut dm = DungeonMap::new(80, 80);
+        dm.create_map(&d);
+        d.add_stairs(&dm, false).unwrap();
+
+        let unreachable = d.get_map_unreachable_rooms(&dm).unwrap();
+        let loops = d.add_secret_doors(&dm, 2).unwrap();
+        let rooms = d.add_hidden_rooms(&dm, 1).unwrap();
+        d.assign_room_roles().unwrap();
+        dm.populate(&d);
+
+        let secret: Vec<usize> = (0..d.get_corridors_number()).filter(|&c| d.is_corridor_secret(c)).collect();
+        assert_eq!(secret.len(), loops + rooms);
+
+        let mut secret_rooms: Vec<usize> = (0..d.get_rooms_number()).map(|r| d.get_room(r).unwrap())
+            .filter(|r| r.role == RoomRole::Secret)
+            .map(|r| r.id)
+            .collect();
+        secret_rooms.sort();
+        assert_eq!(secret_rooms.len(), rooms);
+
+        for &id in secret_rooms.iter()
+        {
+            let neighbours = d.get_room_neighbours(id);
+
+            assert_eq!(neighbours.len(), 1);
+            assert!(d.is_corridor_secret(neighbours[0].0));
+        }
+
+        //Hidden loops cut nothing off, only secret rooms can't be reached until their doors are discovered
+        let mut hidden = d.get_map_unreachable_rooms(&dm).unwrap();
+        hidden.retain(|id| !unreachable.contains(id));
+        hidden.sort();
+
+        assert_eq!(hidden, secret_rooms);
+
+        discover_all(&mut dm);
+
+        assert!(d.get_map_unreachable_rooms(&dm).unwrap().iter().all(|id| unreachable.contains(id)));
+    }
+
+    #[test]
+    fn hidden_room_fixed_map_test()
+    {
+        //Three rooms in a row, each corridor has a door on the left end. The stairs are in rooms 0 and 1
+        let door = |id| Some(Door { id, locked: false, open: false, secret: false });
+        let rooms = vec![Room::new(0, 2, 2, 4, 4), Room::new(1, 12, 2, 4, 4), Room::new(2, 22, 2, 4, 4)];
+        let mut d = Dungeon::from_parts(rooms, vec![Corridor::new(0, 0, 1, door(0), None), Corridor::new(1, 1, 2, door(1), None)]);
+        d.set_stairs(Some(Stairs { room_id: 0, x: 3, y: 3 }), Some(Stairs { room_id: 1, x: 13, y: 3 }));
+
+        let mut dm = DungeonMap::new(30, 10);
+        dm.create_map(&d);
+
+        //There are no loops and only the last room is a dead end with no stairs
+        assert_eq!(d.add_secret_doors(&dm, 1).unwrap(), 0);
+        assert_eq!(d.add_hidden_rooms(&dm, 2).unwrap(), 1);
+        assert_eq!(d.get_room(2).unwrap().role, RoomRole::Secret);
+
+        dm.populate(&d);
+        assert_eq!(d.get_map_unreachable_rooms(&dm), Some(vec![2]));
+
+        discover_all(&mut dm);
+        assert_eq!(d.get_map_unreachable_rooms(&dm), Some(Vec::new()));
+    }
+}
@@ -0,0 +1,208 @@
+use crate::connectivity::DoorPassage;
+use crate::dijkstra::DijkstraMap;
+use crate::door::Door;
+use crate::dungeon::Dungeon;
+use crate::dungeonmap::{DungeonMap, DungeonTile};
+use crate::pathfinding::PathSettings;
+
+/// Stairs leading into or out of the dungeon
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stairs
+{
+    /// Id of the room the stairs stand in
+    pub room_id: usize,
+    /// X position in the dungeon
+    pub x: u16,
+    /// Y position in the dungeon
+    pub y: u16,
+}
+
+impl Dungeon
+{
+    /// Creates stairs on the free cell of the room lying nearest to its middle
+    fn get_stairs_in_room(&self, map: &DungeonMap, room_id: usize) -> Result<Stairs, String>
+    {
+        let room = self.get_room_by_id(room_id).unwrap();
+        let (cx, cy) = ((room.x + room.width / 2) as usize, (room.y + room.height / 2) as usize);
+
+        (room.y as usize..(room.y + room.height) as usize)
+            .flat_map(|y| (room.x as usize..(room.x + room.width) as usize).map(move |x| (x, y)))
+            .filter(|&(x, y)| map.room_at(x, y) == Some(room_id) && map.door_at(x, y).is_none() && map.is_passable(x, y, DoorPassage::OpenOnly))
+            .min_by_key(|&(x, y)| x.abs_diff(cx).pow(2) + y.abs_diff(cy).pow(2))
+            .map(|(x, y)| Stairs { room_id, x: x as u16, y: y as u16 })
+            .ok_or(format!("There is no free cell for stairs in room {}!", room_id))
+    }
+
+    /// Gets walking distances on the map from the room to every other room it is connected with as (room id, distance).
+    /// Every door can be passed, locked ones as if the player had their keys
+    fn get_room_walk_distances(&self, map: &DungeonMap, room_id: usize, settings: &PathSettings) -> Vec<(usize, i32)>
+    {
+        match self.get_room_cell(map, room_id)
+        {
+            Some(cell) => DijkstraMap::new(map, &[cell], settings).get_room_distances(map).into_iter().filter(|&(id, _)| id != room_id).collect(),
+            None => Vec::new()
+        }
+    }
+
+    /// Puts the entrance (stairs up) and the exit (stairs down) in rooms as far from each other as possible,
+    /// measuring the walking distance on the map. Must be called after lock_doors if doors are locked:
+    /// the entrance is then put in the start room the keys were placed for, and only the exit is chosen
+    /// * 'map' - The map created from the dungeon
+    /// * 'exit_behind_lock' - If true the exit is put behind the deepest locked door. It is an error if no door is locked
+    pub fn add_stairs(&mut self, map: &DungeonMap, exit_behind_lock: bool) -> Result<(), String>
+    {
+        if self.get_rooms_number() < 2
+        {
+            return Err("At least two rooms are needed for the entrance and the exit!".to_string());
+        }
+
+        let doors: Vec<Door> = (0..self.get_corridors_number())
+            .map(|c| self.get_corridor(c).unwrap())
+            .flat_map(|c| [c.from_room_door, c.to_room_door].into_iter().flatten())
+            .collect();
+        let locked = doors.iter().any(|d| d.locked);
+
+        if exit_behind_lock && !locked
+        {
+            return Err("There is no locked door to put the exit behind, call lock_doors first!".to_string());
+        }
+
+        let settings = PathSettings { keys: doors.iter().map(|d| d.id).collect(), ..PathSettings::default() };
+
+        let (entrance, exit) = match locked
+        {
+            true =>
+            {
+                let start = self.get_start_room_id().unwrap();
+                let layers = self.explore(start, None);
+                let deepest = layers.values().max().copied().unwrap_or(0);
+
+                let (exit, _) = self.get_room_walk_distances(map, start, &settings).into_iter()
+                    .filter(|(id, _)| !exit_behind_lock || layers.get(id) == Some(&deepest))
+                    .max_by_key(|&(_, d)| d)
+                    .ok_or("There is no room the exit can be put in!")?;
+
+                (start, exit)
+            },
+            false =>
+            {
+                (0..self.get_rooms_number())
+                    .map(|r| self.get_room(r).unwrap().id)
+                    .filter_map(|id| self.get_room_walk_distances(map, id, &settings).into_iter()
+                        .max_by_key(|&(_, d)| d)
+                        .map(|(exit, d)| (id, exit, d)))
+                    .max_by_key(|&(_, _, d)| d)
+                    .map(|(entrance, exit, _)| (entrance, exit))
+                    .ok_or("The rooms are not connected on the map!")?
+            }
+        };
+
+        let entrance = self.get_stairs_in_room(map, entrance)?;
+        let exit = self.get_stairs_in_room(map, exit)?;
+
+        self.set_stairs(Some(entrance), Some(exit));
+
+        Ok(())
+    }
+}
+
+impl DungeonMap
+{
+    /// Puts stairs tiles where the dungeon entrance and exit are. Doorways are never covered with stairs
+    pub(crate) fn create_stairs(&mut self, dungeon: &Dungeon)
+    {
+        for (stairs, tile) in [(dungeon.get_entrance(), DungeonTile::TileStairsUp), (dungeon.get_exit(), DungeonTile::TileStairsDown)]
+        {
+            if let Some(s) = stairs
+            {
+                if self.door_at(s.x as usize, s.y as usize).is_none()
+                {
+                    self.set_tile(s.x as usize, s.y as usize, tile as u8);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::corridor::Corridor;
+    use crate::dungeon::DungeonType;
+    use crate::room::Room;
+
+    #[test]
+    fn add_stairs_test()
+    {
+        let mut d = Dungeon::new();
+        d.generate(10, DungeonType::Basement, 80, 80, 8, 8).unwrap();
+        d.add_doors().unwrap();
+
+        let mut dm = DungeonMap::new(80, 80);
+        dm.create_map(&d);
+
+        assert!(d.add_stairs(&dm, true).is_err());
+        d.add_stairs(&dm, false).unwrap();
+
+        let entrance = d.get_entrance().unwrap();
+        let exit = d.get_exit().unwrap();
+        let settings = PathSettings { keys: (0..d.get_corridors_number() * 2).collect(), ..PathSettings::default() };
+        let distance = d.get_room_walk_distances(&dm, entrance.room_id, &settings).into_iter().find(|&(id, _)| id == exit.room_id).unwrap().1;
+
+        assert_eq!(d.get_start_room_id(), Some(entrance.room_id));
+        assert!((0..d.get_rooms_number()).all(|r| {
+            let id = d.get_room(r).unwrap().id;
+            d.get_room_walk_distances(&dm, id, &settings).iter().all(|&(_, dist)| dist <= distance)
+        }));
+
+        let locked = d.lock_doors(&dm, 2, true).unwrap();
+        d.add_items(true);
+
+        if locked > 0
+        {
+            d.add_stairs(&dm, true).unwrap();
+            assert_eq!(d.get_entrance(), Some(entrance));
+        }
+        dm.populate(&d);
+
+        assert!(d.is_solvable());
+
+        for (stairs, tile) in [(d.get_entrance().unwrap(), DungeonTile::TileStairsUp), (d.get_exit().unwrap(), DungeonTile::TileStairsDown)]
+        {
+            let (x, y) = (stairs.x as usize, stairs.y as usize);
+
+            assert_eq!(dm.room_at(x, y), Some(stairs.room_id));
+            assert_eq!(dm.get_tile(x, y), Some(tile as u8));
+            assert!(dm.get_item_placements().iter().all(|i| (i.x, i.y) != (x, y)));
+        }
+    }
+
+    #[test]
+    fn add_stairs_fixed_map_test()
+    {
+        //Three rooms in a row, each corridor has a door on the left end
+        let door = |id| Some(Door { id, locked: false, open: false, secret: false });
+        let rooms = vec![Room::new(0, 2, 2, 4, 4), Room::new(1, 12, 2, 4, 4), Room::new(2, 22, 2, 4, 4)];
+        let mut d = Dungeon::from_parts(rooms, vec![Corridor::new(0, 0, 1, door(0), None), Corridor::new(1, 1, 2, door(1), None)]);
+
+        let mut dm = DungeonMap::new(30, 10);
+        dm.create_map(&d);
+
+        //The end rooms are the farthest apart
+        d.add_stairs(&dm, false).unwrap();
+        let entrance = d.get_entrance().unwrap();
+        let mut ends = [entrance.room_id, d.get_exit().unwrap().room_id];
+        ends.sort();
+
+        assert_eq!(ends, [0, 2]);
+
+        //Locking keeps the entrance and the exit goes behind the last locked door
+        assert_eq!(d.lock_doors(&dm, 2, true).unwrap(), 2);
+        d.add_stairs(&dm, true).unwrap();
+
+        assert_eq!(d.get_entrance(), Some(entrance));
+        assert_eq!(d.get_exit().unwrap().room_id, 2 - entrance.room_id);
+    }
+}
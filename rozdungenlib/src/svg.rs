@@ -0,0 +1,289 @@
+use crate::decoration::PropKind;
+use crate::dungeon::Dungeon;
+use crate::dungeonmap::{DungeonMap, DungeonTile, Region};
+use crate::item::ItemType;
+use crate::transform::DoorOrientation;
+use crate::xml::escape_xml;
+
+/// Settings of the SVG export
+#[derive(Clone, PartialEq, Debug)]
+pub struct SvgOptions
+{
+    /// Width and height of a map cell in SVG units
+    pub cell_size: u32,
+    /// Write the room id in the middle of every room
+    pub room_labels: bool,
+    /// Draw thin lines between all map cells
+    pub grid: bool,
+}
+
+impl Default for SvgOptions
+{
+    fn default() -> Self
+    {
+        Self { cell_size: 16, room_labels: true, grid: false }
+    }
+}
+
+impl DungeonMap
+{
+    /// Writes the map as an SVG image. Rooms, corridors, props, doors, items, traps and monsters are vector shapes,
+    /// so the image can be printed at any size. Hidden traps are half transparent and secret doors are dashed
+    /// * 'dungeon' - The dungeon the map was created from
+    /// * 'options' - Settings of the export
+    pub fn to_svg(&self, dungeon: &Dungeon, options: &SvgOptions) -> String
+    {
+        let cs = options.cell_size.max(1) as usize;
+        let width = self.get_width() * cs;
+        let height = self.get_height() * cs;
+
+        let mut svg = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n", width, height, width, height);
+        svg.push_str(&format!("  <rect width=\"{}\" height=\"{}\" fill=\"#d0d0d0\"/>\n", width, height));
+
+        svg.push_str("  <g id=\"corridors\" fill=\"#ffffff\">\n");
+        (0..self.get_height()).for_each(|y| {
+            (0..self.get_width()).for_each(|x| {
+                if let Some(Region::Corridor(_)) | Some(Region::Floor) = self.get_region(x, y)
+                {
+                    if self.get_tile(x, y) != Some(DungeonTile::TileWall as u8)
+                    {
+                        svg.push_str(&format!("    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n", x * cs, y * cs, cs, cs));
+                    }
+                }
+            });
+        });
+        svg.push_str("  </g>\n");
+
+        svg.push_str("  <g id=\"rooms\" fill=\"#ffffff\" stroke=\"#000000\" stroke-width=\"2\">\n");
+        for r in 0..dungeon.get_rooms_number()
+        {
+            let room = dungeon.get_room(r).unwrap();
+
+            svg.push_str(&format!("    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+                room.x as usize * cs, room.y as usize * cs, room.width as usize * cs, room.height as usize * cs));
+        }
+        svg.push_str("  </g>\n");
+
+        if options.grid
+        {
+            svg.push_str("  <g id=\"grid\" stroke=\"#a0a0a0\" stroke-width=\"0.5\">\n");
+            (0..=self.get_width()).for_each(|x| {
+                svg.push_str(&format!("    <line x1=\"{}\" y1=\"0\" x2=\"{}\" y2=\"{}\"/>\n", x * cs, x * cs, height));
+            });
+            (0..=self.get_height()).for_each(|y| {
+                svg.push_str(&format!("    <line x1=\"0\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n", y * cs, width, y * cs));
+            });
+            svg.push_str("  </g>\n");
+        }
+
+        svg.push_str("  <g id=\"props\" stroke=\"#000000\" stroke-width=\"1\">\n");
+        (0..self.get_height()).for_each(|y| {
+            (0..self.get_width()).for_each(|x| {
+                if let Some(prop) = self.prop_at(x, y)
+                {
+                    //Colour and the margin left around the prop in eighths of the cell
+                    let (fill, margin) = match prop
+                    {
+                        PropKind::Pillar => ("#808080", 1),
+                        PropKind::Table => ("#a0703c", 1),
+                        PropKind::Bookshelf => ("#5a3a1e", 0),
+                        PropKind::Altar => ("#e0e0e0", 1),
+                        PropKind::Rubble => ("#909090", 3),
+                        PropKind::Bones => ("#f0f0e0", 3),
+                        PropKind::Carpet => ("#a02040", 0),
+                    };
+                    let m = cs * margin / 8;
+
+                    match prop
+                    {
+                        PropKind::Pillar => svg.push_str(&format!("    <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"><title>{}</title></circle>\n",
+                            x * cs + cs / 2, y * cs + cs / 2, cs / 2 - m, fill, prop.get_name())),
+                        _ => svg.push_str(&format!("    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"><title>{}</title></rect>\n",
+                            x * cs + m, y * cs + m, cs - 2 * m, cs - 2 * m, fill, prop.get_name()))
+                    }
+                }
+            });
+        });
+        svg.push_str("  </g>\n");
+
+        svg.push_str("  <g id=\"doors\" stroke=\"#000000\" stroke-width=\"1\">\n");
+        (0..self.get_height()).for_each(|y| {
+            (0..self.get_width()).for_each(|x| {
+                if let Some(door) = self.door_at(x, y)
+                {
+                    let fill = if door.locked { "#c03030" } else if door.open { "#ffffff" } else { "#8c501e" };
+                    let (dw, dh) = if self.get_door_orientation(x, y) == Some(DoorOrientation::Horizontal) { (cs, cs / 3) } else { (cs / 3, cs) };
+                    let dash = if door.secret { " stroke-dasharray=\"2\" fill-opacity=\"0.3\"" } else { "" };
+
+                    svg.push_str(&format!("    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"{}><title>door {}</title></rect>\n",
+                        x * cs + (cs - dw) / 2, y * cs + (cs - dh) / 2, dw, dh, fill, dash, door.id));
+                }
+            });
+        });
+        svg.push_str("  </g>\n");
+
+        svg.push_str("  <g id=\"stairs\" fill=\"#ffffff\" stroke=\"#000000\" stroke-width=\"1\">\n");
+        for (stairs, name) in [(dungeon.get_entrance(), "entrance"), (dungeon.get_exit(), "exit")]
+        {
+            if let Some(s) = stairs
+            {
+                let (x, y) = (s.x as usize * cs, s.y as usize * cs);
+
+                svg.push_str(&format!("    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"><title>{}</title></rect>\n", x, y, cs, cs, name));
+                (1..4).for_each(|i| {
+                    svg.push_str(&format!("    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n", x + cs * i / 4, y, x + cs * i / 4, y + cs));
+                });
+            }
+        }
+        svg.push_str("  </g>\n");
+
+        svg.push_str("  <g id=\"items\" stroke=\"#000000\" stroke-width=\"1\">\n");
+        for placement in self.get_item_placements()
+        {
+            let item = dungeon.get_item_by_id(placement.item_id);
+            let (cx, cy) = (placement.x * cs + cs / 2, placement.y * cs + cs / 2);
+
+            match item.map(|i| i.item_type)
+            {
+                Some(ItemType::Key(_)) =>
+                {
+                    svg.push_str(&format!("    <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"#f0e678\"/>\n", cx - cs / 6, cy, cs / 6));
+                    svg.push_str(&format!("    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n", cx, cy, cx + cs / 3, cy));
+                },
+                _ =>
+                {
+                    svg.push_str(&format!("    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#dcb428\"/>\n",
+                        cx - cs / 3, cy - cs / 4, cs * 2 / 3, cs / 2));
+                }
+            }
+        }
+        svg.push_str("  </g>\n");
+
+        svg.push_str("  <g id=\"traps\" fill=\"#e07020\" stroke=\"#000000\" stroke-width=\"1\">\n");
+        for trap in self.get_traps()
+        {
+            let (x, y) = (trap.x * cs, trap.y * cs);
+            let opacity = if trap.hidden { " opacity=\"0.5\"" } else { "" };
+
+            svg.push_str(&format!("    <polygon points=\"{},{} {},{} {},{}\"{}><title>{}</title></polygon>\n",
+                x + cs / 2, y + cs / 5, x + cs * 4 / 5, y + cs * 4 / 5, x + cs / 5, y + cs * 4 / 5, opacity, escape_xml(trap.kind.get_name())));
+        }
+        svg.push_str("  </g>\n");
+
+        svg.push_str("  <g id=\"monsters\" fill=\"#c03030\" stroke=\"#000000\" stroke-width=\"1\">\n");
+        for placement in self.get_monster_placements()
+        {
+            let kind = dungeon.get_monster_by_id(placement.monster_id).map(|m| m.kind.as_str()).unwrap_or("monster");
+
+            svg.push_str(&format!("    <circle cx=\"{}\" cy=\"{}\" r=\"{}\"><title>{}</title></circle>\n",
+                placement.x * cs + cs / 2, placement.y * cs + cs / 2, cs / 3, escape_xml(kind)));
+        }
+        svg.push_str("  </g>\n");
+
+        if options.room_labels
+        {
+            svg.push_str(&format!("  <g id=\"labels\" font-family=\"sans-serif\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">\n", cs));
+            for r in 0..dungeon.get_rooms_number()
+            {
+                let room = dungeon.get_room(r).unwrap();
+                let cx = room.x as usize * cs + room.width as usize * cs / 2;
+                let cy = room.y as usize * cs + room.height as usize * cs / 2;
+
+                svg.push_str(&format!("    <text x=\"{}\" y=\"{}\">{}</text>\n", cx, cy, room.id));
+            }
+            svg.push_str("  </g>\n");
+        }
+
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::ascii::GlyphTable;
+    use crate::dungeon::DungeonType;
+    use crate::item::{Item, ItemStats};
+    use crate::room::Room;
+    use crate::stairs::Stairs;
+
+    const LEVEL: &str = "\
+##########
+#.O$#..k.#
+#...+..^.#
+#.M.#....#
+##########
+";
+
+    #[test]
+    fn svg_export_test()
+    {
+        let mut d = Dungeon::new();
+        d.generate(5, DungeonType::Basement, 30, 30, 8, 8).unwrap();
+        d.add_doors().unwrap();
+        d.add_items(true);
+
+        let mut dm = DungeonMap::new(30, 30);
+        dm.create_map(&d);
+
+        let svg = dm.to_svg(&d, &SvgOptions { cell_size: 10, room_labels: true, grid: true });
+
+        assert!(svg.contains("viewBox=\"0 0 300 300\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<text ").count(), d.get_rooms_number());
+        assert!(svg.matches("<line ").count() >= 62);
+
+        let plain = dm.to_svg(&d, &SvgOptions::default());
+
+        assert!(!plain.contains("id=\"grid\""));
+    }
+
+    #[test]
+    fn svg_fixed_map_test()
+    {
+        let dm = DungeonMap::from_ascii(LEVEL, &GlyphTable::default()).unwrap();
+        let mut rooms = vec![Room::new(0, 1, 1, 3, 3), Room::new(1, 5, 1, 4, 3)];
+        rooms[0].items.push(Item::new_with_stats(0, ItemType::Weapon, "Sword", ItemStats::default()));
+        rooms[1].items.push(Item::new_with_stats(1, ItemType::Key(0), "Key", ItemStats::default()));
+
+        let mut d = Dungeon::from_parts(rooms, Vec::new());
+        d.set_stairs(Some(Stairs { room_id: 0, x: 1, y: 3 }), Some(Stairs { room_id: 1, x: 8, y: 3 }));
+
+        let svg = dm.to_svg(&d, &SvgOptions { cell_size: 12, room_labels: false, grid: false });
+
+        assert_eq!(get_group(&svg, "props"), ["<circle cx=\"30\" cy=\"18\" r=\"5\" fill=\"#808080\"><title>Pillar</title></circle>"]);
+        assert_eq!(get_group(&svg, "doors"), ["<rect x=\"52\" y=\"24\" width=\"4\" height=\"12\" fill=\"#8c501e\"><title>door 0</title></rect>"]);
+        assert_eq!(get_group(&svg, "stairs"), [
+            "<rect x=\"12\" y=\"36\" width=\"12\" height=\"12\"><title>entrance</title></rect>",
+            "<line x1=\"15\" y1=\"36\" x2=\"15\" y2=\"48\"/>",
+            "<line x1=\"18\" y1=\"36\" x2=\"18\" y2=\"48\"/>",
+            "<line x1=\"21\" y1=\"36\" x2=\"21\" y2=\"48\"/>",
+            "<rect x=\"96\" y=\"36\" width=\"12\" height=\"12\"><title>exit</title></rect>",
+            "<line x1=\"99\" y1=\"36\" x2=\"99\" y2=\"48\"/>",
+            "<line x1=\"102\" y1=\"36\" x2=\"102\" y2=\"48\"/>",
+            "<line x1=\"105\" y1=\"36\" x2=\"105\" y2=\"48\"/>"]);
+        assert_eq!(get_group(&svg, "items"), [
+            "<rect x=\"38\" y=\"15\" width=\"8\" height=\"6\" fill=\"#dcb428\"/>",
+            "<circle cx=\"88\" cy=\"18\" r=\"2\" fill=\"#f0e678\"/>",
+            "<line x1=\"90\" y1=\"18\" x2=\"94\" y2=\"18\"/>"]);
+        assert_eq!(get_group(&svg, "traps"), ["<polygon points=\"90,26 93,33 86,33\" opacity=\"0.5\"><title>Pressure Plate</title></polygon>"]);
+        assert_eq!(get_group(&svg, "monsters"), ["<circle cx=\"30\" cy=\"42\" r=\"4\"><title>monster</title></circle>"]);
+        assert_eq!(get_group(&svg, "rooms").len(), 2);
+        assert!(get_group(&svg, "labels").is_empty());
+    }
+
+    /// Gets the trimmed lines of an SVG group, empty if there is no such group
+    fn get_group<'a>(svg: &'a str, id: &str) -> Vec<&'a str>
+    {
+        svg.lines()
+            .skip_while(|l| !l.contains(&format!("<g id=\"{}\"", id)))
+            .skip(1)
+            .take_while(|l| l.trim() != "</g>")
+            .map(|l| l.trim())
+            .collect()
+    }
+}
@@ -0,0 +1,498 @@
+use crate::dungeon::Dungeon;
+use crate::dungeonmap::{DungeonMap, DungeonTile, Region};
+use crate::item::ItemType;
+use crate::xml::escape_xml;
+
+/// Version of the Tiled map format the maps are written in
+const TILED_VERSION: &str = "1.10";
+/// Number of tile kinds in the tileset
+const TILE_COUNT: u8 = 9;
+
+/// Options of the Tiled map export
+#[derive(Clone, PartialEq, Debug)]
+pub struct TiledOptions
+{
+    /// Width and height of a tile in pixels
+    pub tile_size: u32,
+    /// Directory with tile images laid out like resources/images. No images are referenced if None
+    pub image_dir: Option<String>,
+}
+
+impl Default for TiledOptions
+{
+    fn default() -> Self
+    {
+        Self { tile_size: 32, image_dir: None }
+    }
+}
+
+/// Value of a custom property
+enum PropertyValue
+{
+    Int(usize),
+    Float(f32),
+    Bool(bool),
+    Str(String),
+}
+
+impl PropertyValue
+{
+    fn get_type_name(&self) -> &'static str
+    {
+        match self
+        {
+            PropertyValue::Int(_) => "int",
+            PropertyValue::Float(_) => "float",
+            PropertyValue::Bool(_) => "bool",
+            PropertyValue::Str(_) => "string"
+        }
+    }
+}
+
+/// An object put into one of object layers
+struct MapObject
+{
+    id: usize,
+    name: String,
+    object_type: &'static str,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    properties: Vec<(&'static str, PropertyValue)>,
+}
+
+/// A layer of objects
+struct ObjectLayer
+{
+    name: &'static str,
+    objects: Vec<MapObject>,
+}
+
+/// Gets a name of the item type and the id of the door if the item is a key
+fn get_item_type_name(item_type: ItemType) -> (&'static str, Option<usize>)
+{
+    match item_type
+    {
+        ItemType::Key(door_id) => ("Key", Some(door_id)),
+        ItemType::Weapon => ("Weapon", None),
+        ItemType::Armor => ("Armor", None),
+        ItemType::Potion => ("Potion", None),
+        ItemType::Custom(_) => ("Custom", None)
+    }
+}
+
+/// Gets a name of the image file for the tile
+fn get_tile_image(tile: DungeonTile) -> Option<&'static str>
+{
+    match tile
+    {
+        DungeonTile::TileWall => Some("wall.png"),
+        DungeonTile::TileClosedDoor => Some("door_closed.png"),
+        DungeonTile::TileOpenDoor => Some("door_open.png"),
+        DungeonTile::TileChest => Some("chest.png"),
+        DungeonTile::TileKey => Some("key.png"),
+        _ => None
+    }
+}
+
+fn escape_json(text: &str) -> String
+{
+    let mut escaped = String::with_capacity(text.len());
+
+    text.chars().for_each(|c| {
+        match c
+        {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c)
+        }
+    });
+
+    escaped
+}
+
+impl DungeonMap
+{
+    /// Gets global tile ids of all cells row by row. The void gets 0 which is an empty cell in Tiled
+    fn get_tile_gids(&self) -> Vec<u32>
+    {
+        let mut gids: Vec<u32> = Vec::with_capacity(self.get_width() * self.get_height());
+
+        (0..self.get_height()).for_each(|y| {
+            (0..self.get_width()).for_each(|x| {
+                let tile = self.get_tile(x, y).unwrap();
+                let void = tile == DungeonTile::TileEmpty as u8 && self.get_region(x, y) == Some(Region::Void);
+
+                gids.push(if void { 0 } else { tile as u32 + 1 });
+            });
+        });
+
+        gids
+    }
+
+    /// Creates object layers of rooms, doors, items, monsters, traps and props
+    fn get_object_layers(&self, dungeon: &Dungeon, tile_size: u32) -> Vec<ObjectLayer>
+    {
+        let mut object_id = 1;
+        let mut rooms = ObjectLayer { name: "Rooms", objects: Vec::new() };
+        let mut doors = ObjectLayer { name: "Doors", objects: Vec::new() };
+        let mut items = ObjectLayer { name: "Items", objects: Vec::new() };
+        let mut monsters = ObjectLayer { name: "Monsters", objects: Vec::new() };
+        let mut traps = ObjectLayer { name: "Traps", objects: Vec::new() };
+        let mut props = ObjectLayer { name: "Props", objects: Vec::new() };
+
+        for r in 0..dungeon.get_rooms_number()
+        {
+            let room = dungeon.get_room(r).unwrap();
+
+            rooms.objects.push(MapObject {
+                id: object_id,
+                name: format!("Room {}", room.id),
+                object_type: "room",
+                x: room.x as u32 * tile_size,
+                y: room.y as u32 * tile_size,
+                width: room.width as u32 * tile_size,
+                height: room.height as u32 * tile_size,
+                properties: vec![("id", PropertyValue::Int(room.id)), ("items", PropertyValue::Int(room.items.len())),
+                    ("role", PropertyValue::Str(room.role.get_name().to_string()))],
+            });
+            object_id += 1;
+        }
+
+        (0..self.get_height()).for_each(|y| {
+            (0..self.get_width()).for_each(|x| {
+                if let Some(door) = self.door_at(x, y)
+                {
+                    doors.objects.push(MapObject {
+                        id: object_id,
+                        name: format!("Door {}", door.id),
+                        object_type: "door",
+                        x: x as u32 * tile_size,
+                        y: y as u32 * tile_size,
+                        width: tile_size,
+                        height: tile_size,
+                        properties: vec![("id", PropertyValue::Int(door.id)), ("locked", PropertyValue::Bool(door.locked)),
+                            ("open", PropertyValue::Bool(door.open)), ("secret", PropertyValue::Bool(door.secret))],
+                    });
+                    object_id += 1;
+                }
+            });
+        });
+
+        for placement in self.get_item_placements()
+        {
+            if let Some(item) = dungeon.get_item_by_id(placement.item_id)
+            {
+                let (type_name, door_id) = get_item_type_name(item.item_type);
+                let mut properties = vec![("id", PropertyValue::Int(item.id)), ("type", PropertyValue::Str(type_name.to_string())),
+                    ("room", PropertyValue::Int(placement.room_id))];
+
+                if let Some(door_id) = door_id
+                {
+                    properties.push(("door", PropertyValue::Int(door_id)));
+                }
+
+                if let ItemType::Custom(kind_id) = item.item_type
+                {
+                    properties.push(("kind", PropertyValue::Int(kind_id as usize)));
+                }
+
+                properties.extend([("damage", PropertyValue::Int(item.stats.damage as usize)),
+                    ("defence", PropertyValue::Int(item.stats.defence as usize)),
+                    ("value", PropertyValue::Int(item.stats.value as usize)),
+                    ("weight", PropertyValue::Float(item.stats.weight)),
+                    ("stack", PropertyValue::Int(item.stats.stack as usize))]);
+
+                if let Some(effect) = &item.stats.effect
+                {
+                    properties.push(("effect", PropertyValue::Str(effect.clone())));
+                }
+
+                items.objects.push(MapObject {
+                    id: object_id,
+                    name: item.desc.clone(),
+                    object_type: "item",
+                    x: placement.x as u32 * tile_size,
+                    y: placement.y as u32 * tile_size,
+                    width: tile_size,
+                    height: tile_size,
+                    properties,
+                });
+                object_id += 1;
+            }
+        }
+
+        for placement in self.get_monster_placements()
+        {
+            if let Some(monster) = dungeon.get_monster_by_id(placement.monster_id)
+            {
+                monsters.objects.push(MapObject {
+                    id: object_id,
+                    name: monster.kind.clone(),
+                    object_type: "monster",
+                    x: placement.x as u32 * tile_size,
+                    y: placement.y as u32 * tile_size,
+                    width: tile_size,
+                    height: tile_size,
+                    properties: vec![("id", PropertyValue::Int(monster.id)), ("level", PropertyValue::Int(monster.level as usize)),
+                        ("hp", PropertyValue::Int(monster.hp as usize)), ("room", PropertyValue::Int(placement.room_id))],
+                });
+                object_id += 1;
+            }
+        }
+
+        for trap in self.get_traps()
+        {
+            let (left, top) = (trap.x.saturating_sub(trap.trigger_radius), trap.y.saturating_sub(trap.trigger_radius));
+
+            //The object covers the trigger area of the trap
+            traps.objects.push(MapObject {
+                id: object_id,
+                name: trap.kind.get_name().to_string(),
+                object_type: "trap",
+                x: left as u32 * tile_size,
+                y: top as u32 * tile_size,
+                width: (trap.x + trap.trigger_radius + 1 - left) as u32 * tile_size,
+                height: (trap.y + trap.trigger_radius + 1 - top) as u32 * tile_size,
+                properties: vec![("id", PropertyValue::Int(trap.id)), ("effect", PropertyValue::Str(trap.effect.clone())),
+                    ("damage", PropertyValue::Int(trap.damage as usize)), ("hidden", PropertyValue::Bool(trap.hidden))],
+            });
+            object_id += 1;
+        }
+
+        (0..self.get_height()).for_each(|y| {
+            (0..self.get_width()).for_each(|x| {
+                if let Some(prop) = self.prop_at(x, y)
+                {
+                    props.objects.push(MapObject {
+                        id: object_id,
+                        name: prop.get_name().to_string(),
+                        object_type: "prop",
+                        x: x as u32 * tile_size,
+                        y: y as u32 * tile_size,
+                        width: tile_size,
+                        height: tile_size,
+                        properties: vec![("blocking", PropertyValue::Bool(prop.is_blocking()))],
+                    });
+                    object_id += 1;
+                }
+            });
+        });
+
+        vec![rooms, doors, items, monsters, traps, props]
+    }
+
+    /// Writes the map as a Tiled TMX map. Tiles go into the "Tiles" layer, rooms, doors, items, monsters, traps and props
+    /// go into object layers with their ids and types as custom properties
+    /// * 'dungeon' - The dungeon the map was created from
+    /// * 'options' - Export options
+    pub fn to_tmx(&self, dungeon: &Dungeon, options: &TiledOptions) -> String
+    {
+        let size = options.tile_size;
+        let layers = self.get_object_layers(dungeon, size);
+        let next_object_id = layers.iter().map(|l| l.objects.len()).sum::<usize>() + 1;
+        let mut xml = String::new();
+
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!("<map version=\"{}\" orientation=\"orthogonal\" renderorder=\"right-down\" width=\"{}\" height=\"{}\" \
+            tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" nextlayerid=\"{}\" nextobjectid=\"{}\">\n",
+            TILED_VERSION, self.get_width(), self.get_height(), size, size, layers.len() + 2, next_object_id));
+
+        xml.push_str(&format!(" <tileset firstgid=\"1\" name=\"rozdungen\" tilewidth=\"{}\" tileheight=\"{}\" tilecount=\"{}\" columns=\"0\">\n",
+            size, size, TILE_COUNT));
+
+        for tile in (0..TILE_COUNT).filter_map(DungeonTile::from_u8)
+        {
+            let image = options.image_dir.as_ref().zip(get_tile_image(tile));
+
+            match image
+            {
+                Some((dir, file)) =>
+                {
+                    xml.push_str(&format!("  <tile id=\"{}\" type=\"{:?}\">\n", tile as u8, tile));
+                    xml.push_str(&format!("   <image width=\"{}\" height=\"{}\" source=\"{}/{}\"/>\n", size, size, escape_xml(dir), file));
+                    xml.push_str("  </tile>\n");
+                },
+                None => xml.push_str(&format!("  <tile id=\"{}\" type=\"{:?}\"/>\n", tile as u8, tile))
+            }
+        }
+
+        xml.push_str(" </tileset>\n");
+
+        let gids = self.get_tile_gids();
+        let rows: Vec<String> = gids.chunks(self.get_width())
+            .map(|row| row.iter().map(|g| g.to_string()).collect::<Vec<String>>().join(","))
+            .collect();
+
+        xml.push_str(&format!(" <layer id=\"1\" name=\"Tiles\" width=\"{}\" height=\"{}\">\n", self.get_width(), self.get_height()));
+        xml.push_str("  <data encoding=\"csv\">\n");
+        xml.push_str(&rows.join(",\n"));
+        xml.push_str("\n  </data>\n </layer>\n");
+
+        for (i, layer) in layers.iter().enumerate()
+        {
+            xml.push_str(&format!(" <objectgroup id=\"{}\" name=\"{}\">\n", i + 2, layer.name));
+
+            for o in layer.objects.iter()
+            {
+                xml.push_str(&format!("  <object id=\"{}\" name=\"{}\" type=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\">\n",
+                    o.id, escape_xml(&o.name), o.object_type, o.x, o.y, o.width, o.height));
+                xml.push_str("   <properties>\n");
+
+                for (name, value) in o.properties.iter()
+                {
+                    let text = match value
+                    {
+                        PropertyValue::Int(v) => v.to_string(),
+                        PropertyValue::Float(v) => v.to_string(),
+                        PropertyValue::Bool(v) => v.to_string(),
+                        PropertyValue::Str(v) => escape_xml(v)
+                    };
+
+                    xml.push_str(&format!("    <property name=\"{}\" type=\"{}\" value=\"{}\"/>\n", name, value.get_type_name(), text));
+                }
+
+                xml.push_str("   </properties>\n  </object>\n");
+            }
+
+            xml.push_str(" </objectgroup>\n");
+        }
+
+        xml.push_str("</map>\n");
+
+        xml
+    }
+
+    /// Writes the map as a Tiled JSON map with the same layers as the TMX one
+    /// * 'dungeon' - The dungeon the map was created from
+    /// * 'options' - Export options
+    pub fn to_tiled_json(&self, dungeon: &Dungeon, options: &TiledOptions) -> String
+    {
+        let size = options.tile_size;
+        let layers = self.get_object_layers(dungeon, size);
+        let next_object_id = layers.iter().map(|l| l.objects.len()).sum::<usize>() + 1;
+
+        let tiles: Vec<String> = (0..TILE_COUNT).filter_map(DungeonTile::from_u8).map(|tile| {
+            match options.image_dir.as_ref().zip(get_tile_image(tile))
+            {
+                Some((dir, file)) => format!("{{\"id\":{},\"type\":\"{:?}\",\"image\":\"{}/{}\",\"imagewidth\":{},\"imageheight\":{}}}",
+                    tile as u8, tile, escape_json(dir), file, size, size),
+                None => format!("{{\"id\":{},\"type\":\"{:?}\"}}", tile as u8, tile)
+            }
+        }).collect();
+
+        let gids: Vec<String> = self.get_tile_gids().iter().map(|g| g.to_string()).collect();
+
+        let mut json_layers: Vec<String> = vec![format!("{{\"id\":1,\"name\":\"Tiles\",\"type\":\"tilelayer\",\"x\":0,\"y\":0,\
+            \"width\":{},\"height\":{},\"opacity\":1,\"visible\":true,\"data\":[{}]}}",
+            self.get_width(), self.get_height(), gids.join(","))];
+
+        for (i, layer) in layers.iter().enumerate()
+        {
+            let objects: Vec<String> = layer.objects.iter().map(|o| {
+                let properties: Vec<String> = o.properties.iter().map(|(name, value)| {
+                    let text = match value
+                    {
+                        PropertyValue::Int(v) => v.to_string(),
+                        PropertyValue::Float(v) => v.to_string(),
+                        PropertyValue::Bool(v) => v.to_string(),
+                        PropertyValue::Str(v) => format!("\"{}\"", escape_json(v))
+                    };
+
+                    format!("{{\"name\":\"{}\",\"type\":\"{}\",\"value\":{}}}", name, value.get_type_name(), text)
+                }).collect();
+
+                format!("{{\"id\":{},\"name\":\"{}\",\"type\":\"{}\",\"x\":{},\"y\":{},\"width\":{},\"height\":{},\
+                    \"rotation\":0,\"visible\":true,\"properties\":[{}]}}",
+                    o.id, escape_json(&o.name), o.object_type, o.x, o.y, o.width, o.height, properties.join(","))
+            }).collect();
+
+            json_layers.push(format!("{{\"id\":{},\"name\":\"{}\",\"type\":\"objectgroup\",\"draworder\":\"topdown\",\
+                \"x\":0,\"y\":0,\"opacity\":1,\"visible\":true,\"objects\":[{}]}}",
+                i + 2, layer.name, objects.join(",")));
+        }
+
+        format!("{{\"type\":\"map\",\"version\":\"{}\",\"orientation\":\"orthogonal\",\"renderorder\":\"right-down\",\
+            \"width\":{},\"height\":{},\"tilewidth\":{},\"tileheight\":{},\"infinite\":false,\"nextlayerid\":{},\"nextobjectid\":{},\
+            \"tilesets\":[{{\"firstgid\":1,\"name\":\"rozdungen\",\"tilewidth\":{},\"tileheight\":{},\"tilecount\":{},\"columns\":0,\"tiles\":[{}]}}],\
+            \"layers\":[{}]}}\n",
+            TILED_VERSION, self.get_width(), self.get_height(), size, size, layers.len() + 2, next_object_id,
+            size, size, TILE_COUNT, tiles.join(","), json_layers.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::ascii::GlyphTable;
+    use crate::item::{Item, ItemStats};
+    use crate::monster::Monster;
+    use crate::room::Room;
+
+    const LEVEL: &str = "\
+##########
+#.O$#..k.#
+#...+..^.#
+#.M.#....#
+##########
+";
+
+    #[test]
+    fn tiled_export_test()
+    {
+        let odd_name = "A \"quoted\" <item> \\ & co";
+        let mut rooms = vec![Room::new(0, 1, 1, 3, 3), Room::new(1, 5, 1, 4, 3)];
+        rooms[0].items.push(Item::new_with_stats(0, ItemType::Potion, odd_name, ItemStats::default()));
+        rooms[0].monsters.push(Monster::new(0, "Goblin", 1, 5, 1));
+        rooms[1].items.push(Item::new_with_stats(1, ItemType::Key(0), "Key", ItemStats::default()));
+
+        let d = Dungeon::from_parts(rooms, Vec::new());
+        let dm = DungeonMap::from_ascii(LEVEL, &GlyphTable::default()).unwrap();
+        let options = TiledOptions { tile_size: 16, image_dir: Some("images".to_string()) };
+
+        //2 rooms, a door, a chest and a key, a monster, a trap and a pillar
+        let tmx = dm.to_tmx(&d, &options);
+        assert_eq!(tmx.matches("<object ").count(), 8);
+        assert!(tmx.contains("source=\"images/wall.png\""));
+        assert_eq!(tmx.matches("name=\"A &quot;quoted&quot; &lt;item&gt; \\ &amp; co\"").count(), 1);
+
+        let json: serde_json::Value = serde_json::from_str(&dm.to_tiled_json(&d, &options)).unwrap();
+        let layers = json["layers"].as_array().unwrap();
+        let names: Vec<&str> = layers.iter().map(|l| l["name"].as_str().unwrap()).collect();
+
+        assert_eq!(names, ["Tiles", "Rooms", "Doors", "Items", "Monsters", "Traps", "Props"]);
+        assert_eq!(json["width"], 10);
+        assert_eq!(json["nextobjectid"], 9);
+        assert_eq!(layers[0]["data"].as_array().unwrap().len(), 10 * 5);
+        assert_eq!(json["tilesets"][0]["tiles"][1]["image"], "images/wall.png");
+
+        //Objects are numbered from 1 through all layers
+        let objects: Vec<Vec<(u64, &str)>> = layers[1..].iter()
+            .map(|l| l["objects"].as_array().unwrap().iter().map(|o| (o["id"].as_u64().unwrap(), o["name"].as_str().unwrap())).collect())
+            .collect();
+
+        assert_eq!(objects, [vec![(1, "Room 0"), (2, "Room 1")], vec![(3, "Door 0")], vec![(4, odd_name), (5, "Key")],
+            vec![(6, "Goblin")], vec![(7, "Pressure Plate")], vec![(8, "Pillar")]]);
+
+        for object in layers[1..].iter().flat_map(|l| l["objects"].as_array().unwrap())
+        {
+            for property in object["properties"].as_array().unwrap()
+            {
+                let matching = match property["type"].as_str().unwrap()
+                {
+                    "int" => property["value"].is_u64(),
+                    "float" => property["value"].is_number(),
+                    "bool" => property["value"].is_boolean(),
+                    "string" => property["value"].is_string(),
+                    _ => false
+                };
+
+                assert!(matching);
+            }
+        }
+    }
+}
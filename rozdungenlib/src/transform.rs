@@ -0,0 +1,442 @@
+use crate::dungeon::Dungeon;
+use crate::dungeonmap::{DungeonMap, DungeonTile, ItemPlacement, Region};
+use crate::monster::MonsterPlacement;
+use crate::stairs::Stairs;
+use crate::trap::Trap;
+
+/// Rotation or mirroring of the whole dungeon
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Transform
+{
+    Rotate90,           //Clockwise rotation by 90 degrees
+    Rotate180,
+    Rotate270,          //Clockwise rotation by 270 degrees
+    MirrorHorizontal,   //Left and right sides are swapped
+    MirrorVertical,     //Top and bottom sides are swapped
+}
+
+/// Tells in which wall a door stands
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DoorOrientation
+{
+    Horizontal,     //The door has walls on its left and right side and is passed from top to bottom
+    Vertical,       //The door has walls above and below and is passed from left to right
+}
+
+impl Transform
+{
+    /// Gets the size of an area after the transformation
+    /// * 'width' - Width of the area
+    /// * 'height' - Height of the area
+    pub fn get_size(&self, width: usize, height: usize) -> (usize, usize)
+    {
+        match self
+        {
+            Transform::Rotate90 | Transform::Rotate270 => (height, width),
+            _ => (width, height)
+        }
+    }
+
+    /// Gets a new position of a point
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    /// * 'width' - Width of the area the point lies in
+    /// * 'height' - Height of the area the point lies in
+    pub fn apply(&self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize)
+    {
+        match self
+        {
+            Transform::Rotate90 => (height - 1 - y, x),
+            Transform::Rotate180 => (width - 1 - x, height - 1 - y),
+            Transform::Rotate270 => (y, width - 1 - x),
+            Transform::MirrorHorizontal => (width - 1 - x, y),
+            Transform::MirrorVertical => (x, height - 1 - y)
+        }
+    }
+}
+
+impl DoorOrientation
+{
+    /// Gets the orientation of a door after the transformation
+    /// * 'transform' - The transformation
+    pub fn transformed(&self, transform: Transform) -> Self
+    {
+        match (transform, self)
+        {
+            (Transform::Rotate90 | Transform::Rotate270, DoorOrientation::Horizontal) => DoorOrientation::Vertical,
+            (Transform::Rotate90 | Transform::Rotate270, DoorOrientation::Vertical) => DoorOrientation::Horizontal,
+            _ => *self
+        }
+    }
+}
+
+impl DungeonMap
+{
+    /// Creates a new map of given size with every cell moved to the position returned by the function.
+    /// Cells moved to None or outside of the new map are dropped, cells nothing is moved to are walls
+    pub(crate) fn remap<F>(&self, width: usize, height: usize, f: F) -> DungeonMap
+        where F: Fn(usize, usize) -> Option<(usize, usize)>
+    {
+        let mut map = DungeonMap::new(width, height);
+
+        (0..self.get_height()).for_each(|y| {
+            (0..self.get_width()).for_each(|x| {
+                if let Some((nx, ny)) = f(x, y)
+                {
+                    map.set_tile(nx, ny, self.get_tile(x, y).unwrap());
+                    map.set_region(nx, ny, self.get_region(x, y).unwrap());
+                    map.set_feature(nx, ny, self.get_feature(x, y).unwrap());
+                }
+            });
+        });
+
+        let items = self.get_item_placements().iter()
+            .filter_map(|i| match f(i.x, i.y)
+            {
+                Some((x, y)) if x < width && y < height => Some(ItemPlacement { x, y, ..*i }),
+                _ => None
+            })
+            .collect();
+
+        map.set_item_placements(items);
+
+        let monsters = self.get_monster_placements().iter()
+            .filter_map(|m| match f(m.x, m.y)
+            {
+                Some((x, y)) if x < width && y < height => Some(MonsterPlacement { x, y, ..*m }),
+                _ => None
+            })
+            .collect();
+
+        map.set_monster_placements(monsters);
+
+        let traps = self.get_traps().iter()
+            .filter_map(|t| match f(t.x, t.y)
+            {
+                Some((x, y)) if x < width && y < height => Some(Trap { x, y, ..t.clone() }),
+                _ => None
+            })
+            .collect();
+
+        map.set_traps(traps);
+
+        map
+    }
+
+    /// Gets the smallest rectangle holding all cells that are neither walls nor the void
+    /// as (left, top, right, bottom), all inclusive. None if there are no such cells
+    pub fn get_content_bounds(&self) -> Option<(usize, usize, usize, usize)>
+    {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+
+        (0..self.get_height()).for_each(|y| {
+            (0..self.get_width()).for_each(|x| {
+                let wall = self.get_tile(x, y) == Some(DungeonTile::TileWall as u8);
+                let void = self.get_region(x, y) == Some(Region::Void);
+
+                if !wall && !void
+                {
+                    bounds = Some(match bounds
+                    {
+                        Some((l, t, r, b)) => (l.min(x), t.min(y), r.max(x), b.max(y)),
+                        None => (x, y, x, y)
+                    });
+                }
+            });
+        });
+
+        bounds
+    }
+
+    /// Cuts a rectangle out of the map. Item and monster placements are moved with the cells
+    /// * 'x' - Left edge of the rectangle
+    /// * 'y' - Top edge of the rectangle
+    /// * 'width' - Width of the rectangle
+    /// * 'height' - Height of the rectangle
+    pub fn crop(&mut self, x: usize, y: usize, width: usize, height: usize) -> Result<(), String>
+    {
+        if width == 0 || height == 0
+        {
+            return Err("The cropped map must not be empty!".to_string());
+        }
+
+        if x + width > self.get_width() || y + height > self.get_height()
+        {
+            return Err("The crop rectangle doesn't fit into the map!".to_string());
+        }
+
+        *self = self.remap(width, height, |cx, cy| {
+            match cx >= x && cy >= y
+            {
+                true => Some((cx - x, cy - y)),
+                false => None
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Cuts off everything around the rooms and corridors, walls included. Returns how far the map
+    /// was moved to the left and to the top, so the dungeon can be moved the same way
+    pub fn trim(&mut self) -> Result<(usize, usize), String>
+    {
+        let (left, top, right, bottom) = self.get_content_bounds().ok_or("The map has no rooms or corridors!")?;
+
+        self.crop(left, top, right - left + 1, bottom - top + 1)?;
+
+        Ok((left, top))
+    }
+
+    /// Surrounds the map with walls
+    /// * 'border' - Thickness of the wall in tiles
+    pub fn pad(&mut self, border: usize)
+    {
+        let width = self.get_width() + 2 * border;
+        let height = self.get_height() + 2 * border;
+
+        *self = self.remap(width, height, |x, y| Some((x + border, y + border)));
+    }
+
+    /// Gets the orientation of the door standing at given position
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn get_door_orientation(&self, x: usize, y: usize) -> Option<DoorOrientation>
+    {
+        self.door_at(x, y)?;
+
+        let is_wall = |x: usize, y: usize| self.get_tile(x, y) == Some(DungeonTile::TileWall as u8);
+
+        if x > 0 && is_wall(x - 1, y) && is_wall(x + 1, y)
+        {
+            Some(DoorOrientation::Horizontal)
+        }
+        else if y > 0 && is_wall(x, y - 1) && is_wall(x, y + 1)
+        {
+            Some(DoorOrientation::Vertical)
+        }
+        else
+        {
+            None
+        }
+    }
+
+    /// Rotates or mirrors the map grid with all regions, item and monster placements.
+    /// Doors keep their walls, so their orientation changes like DoorOrientation::transformed says
+    /// * 'transform' - The transformation
+    pub fn transform_grid(&mut self, transform: Transform)
+    {
+        let (width, height) = (self.get_width(), self.get_height());
+        let (new_width, new_height) = transform.get_size(width, height);
+
+        *self = self.remap(new_width, new_height, |x, y| Some(transform.apply(x, y, width, height)));
+    }
+
+    /// Rotates or mirrors both the map and the dungeon it was created from
+    /// * 'dungeon' - The dungeon the map was created from
+    /// * 'transform' - The transformation
+    pub fn transform(&mut self, dungeon: &mut Dungeon, transform: Transform) -> Result<(), String>
+    {
+        dungeon.transform(transform, self.get_width(), self.get_height())?;
+        self.transform_grid(transform);
+
+        Ok(())
+    }
+
+    /// Trims the map, surrounds it with walls and moves the dungeon to match the new map
+    /// * 'dungeon' - The dungeon the map was created from
+    /// * 'border' - Thickness of the wall around the map in tiles
+    pub fn compact(&mut self, dungeon: &mut Dungeon, border: usize) -> Result<(), String>
+    {
+        let (left, top, _, _) = self.get_content_bounds().ok_or("The map has no rooms or corridors!")?;
+
+        dungeon.translate(border as i32 - left as i32, border as i32 - top as i32)?;
+        self.trim()?;
+        self.pad(border);
+
+        Ok(())
+    }
+}
+
+impl Dungeon
+{
+    /// Moves all rooms, lights and stairs of the dungeon
+    /// * 'dx' - Distance to move along the X axis
+    /// * 'dy' - Distance to move along the Y axis
+    pub fn translate(&mut self, dx: i32, dy: i32) -> Result<(), String>
+    {
+        let moved = |v: u16, d: i32| u16::try_from(v as i32 + d).ok();
+
+        for r in 0..self.get_rooms_number()
+        {
+            let room = self.get_room(r).unwrap();
+
+            if moved(room.x, dx).is_none() || moved(room.y, dy).is_none()
+                || room.lights.iter().any(|l| moved(l.x, dx).is_none() || moved(l.y, dy).is_none())
+            {
+                return Err(format!("Room {} can't be moved outside of the dungeon!", room.id));
+            }
+        }
+
+        for r in 0..self.get_rooms_number()
+        {
+            let room = self.get_room_mut(r).unwrap();
+
+            room.x = moved(room.x, dx).unwrap();
+            room.y = moved(room.y, dy).unwrap();
+
+            room.lights.iter_mut().for_each(|l| {
+                l.x = moved(l.x, dx).unwrap();
+                l.y = moved(l.y, dy).unwrap();
+            });
+        }
+
+        //Stairs stand in rooms, so they can always be moved
+        let move_stairs = |s: Stairs| Stairs { x: moved(s.x, dx).unwrap(), y: moved(s.y, dy).unwrap(), ..s };
+        self.set_stairs(self.get_entrance().map(move_stairs), self.get_exit().map(move_stairs));
+
+        Ok(())
+    }
+
+    /// Rotates or mirrors rooms, lights and stairs of the dungeon inside an area, usually the map.
+    /// Corridors and doors have no positions in the dungeon, they are moved with the map
+    /// * 'transform' - The transformation
+    /// * 'width' - Width of the area
+    /// * 'height' - Height of the area
+    pub fn transform(&mut self, transform: Transform, width: usize, height: usize) -> Result<(), String>
+    {
+        for r in 0..self.get_rooms_number()
+        {
+            let room = self.get_room(r).unwrap();
+
+            if room.x as usize + room.width as usize > width || room.y as usize + room.height as usize > height
+            {
+                return Err(format!("Room {} doesn't fit into the area!", room.id));
+            }
+        }
+
+        let point = |x: u16, y: u16| {
+            let (nx, ny) = transform.apply((x as usize).min(width - 1), (y as usize).min(height - 1), width, height);
+            (nx as u16, ny as u16)
+        };
+
+        for r in 0..self.get_rooms_number()
+        {
+            let room = self.get_room_mut(r).unwrap();
+
+            if room.width == 0 || room.height == 0
+            {
+                continue;
+            }
+
+            let (x1, y1) = point(room.x, room.y);
+            let (x2, y2) = point(room.x + room.width - 1, room.y + room.height - 1);
+
+            room.x = x1.min(x2);
+            room.y = y1.min(y2);
+            room.width = x1.abs_diff(x2) + 1;
+            room.height = y1.abs_diff(y2) + 1;
+
+            room.lights.iter_mut().for_each(|l| (l.x, l.y) = point(l.x, l.y));
+        }
+
+        let move_stairs = |s: Stairs| {
+            let (x, y) = point(s.x, s.y);
+            Stairs { x, y, ..s }
+        };
+        self.set_stairs(self.get_entrance().map(move_stairs), self.get_exit().map(move_stairs));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::dungeon::DungeonType;
+
+    #[test]
+    fn compact_test()
+    {
+        let mut d = Dungeon::new();
+        d.generate(6, DungeonType::Basement, 60, 60, 8, 8).unwrap();
+        d.add_doors().unwrap();
+        d.add_items(true);
+
+        let mut dm = DungeonMap::new(60, 60);
+        dm.create_map(&d);
+
+        let (left, top, right, bottom) = dm.get_content_bounds().unwrap();
+        let items = dm.get_item_placements().to_vec();
+        let original = dm.clone();
+        let (room_x, room_y) = (d.get_room(0).unwrap().x as usize, d.get_room(0).unwrap().y as usize);
+
+        dm.compact(&mut d, 1).unwrap();
+
+        assert_eq!(dm.get_width(), right - left + 3);
+        assert_eq!(dm.get_height(), bottom - top + 3);
+        assert_eq!(dm.get_content_bounds(), Some((1, 1, right - left + 1, bottom - top + 1)));
+
+        //The corner of the room moves with the room, whatever covers it
+        let room = d.get_room(0).unwrap();
+        assert_eq!((room.x as usize, room.y as usize), (room_x - left + 1, room_y - top + 1));
+        assert_eq!(dm.get_region(room.x as usize, room.y as usize), original.get_region(room_x, room_y));
+
+        items.iter().zip(dm.get_item_placements()).for_each(|(a, b)| {
+            assert_eq!((a.x - left + 1, a.y - top + 1), (b.x, b.y));
+        });
+
+        assert!(d.translate(-1000, 0).is_err());
+    }
+
+    #[test]
+    fn transform_test()
+    {
+        let mut d = Dungeon::new();
+        d.generate(6, DungeonType::Basement, 50, 40, 8, 8).unwrap();
+        d.add_doors().unwrap();
+        d.add_items(true);
+
+        let mut dm = DungeonMap::new(50, 40);
+        dm.create_map(&d);
+
+        let original = dm.clone();
+        let door = (0..40).flat_map(|y| (0..50).map(move |x| (x, y))).find(|&(x, y)| original.get_door_orientation(x, y).is_some());
+        let corners: Vec<((usize, usize), (usize, usize))> = (0..d.get_rooms_number()).map(|r| {
+            let room = d.get_room(r).unwrap();
+            ((room.x as usize, room.y as usize), ((room.x + room.width - 1) as usize, (room.y + room.height - 1) as usize))
+        }).collect();
+
+        dm.transform(&mut d, Transform::Rotate90).unwrap();
+
+        assert_eq!((dm.get_width(), dm.get_height()), (40, 50));
+        assert_eq!(dm.get_tile(39, 0), original.get_tile(0, 0));
+        assert_eq!(dm.get_item_placements().len(), original.get_item_placements().len());
+
+        //Opposite corners of a room swap places, but they keep what covers them
+        (0..d.get_rooms_number()).for_each(|r| {
+            let room = d.get_room(r).unwrap();
+            let (top_left, bottom_right) = corners[r];
+            let (x0, y0) = Transform::Rotate90.apply(top_left.0, top_left.1, 50, 40);
+            let (x1, y1) = Transform::Rotate90.apply(bottom_right.0, bottom_right.1, 50, 40);
+
+            assert_eq!((room.x as usize, room.y as usize), (x0.min(x1), y0.min(y1)));
+            assert_eq!(dm.get_region(x0, y0), original.get_region(top_left.0, top_left.1));
+            assert_eq!(dm.get_region(x1, y1), original.get_region(bottom_right.0, bottom_right.1));
+        });
+
+        if let Some((x, y)) = door
+        {
+            let (nx, ny) = Transform::Rotate90.apply(x, y, 50, 40);
+            let orientation = original.get_door_orientation(x, y).unwrap();
+
+            assert_eq!(dm.get_door_orientation(nx, ny), Some(orientation.transformed(Transform::Rotate90)));
+        }
+
+        dm.transform(&mut d, Transform::Rotate270).unwrap();
+        dm.transform(&mut d, Transform::MirrorHorizontal).unwrap();
+        dm.transform(&mut d, Transform::MirrorHorizontal).unwrap();
+
+        assert_eq!(dm.to_ascii(&Default::default()), original.to_ascii(&Default::default()));
+    }
+}
@@ -0,0 +1,347 @@
+use rand::thread_rng;
+use rand::seq::SliceRandom;
+
+use crate::connectivity::DoorPassage;
+use crate::dungeon::Dungeon;
+use crate::dungeonmap::{DungeonMap, DungeonTile, Feature, Region};
+
+/// Kinds of traps that can be put on the map
+pub const TRAP_KINDS: [TrapKind; 4] = [TrapKind::PressurePlate, TrapKind::Pit, TrapKind::DartWall, TrapKind::AlarmRune];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrapKind
+{
+    PressurePlate,  //Shoots arrows when stepped on
+    Pit,            //A hole covered with a thin floor
+    DartWall,       //Poisoned darts fly out of the walls around
+    AlarmRune,      //Wakes up monsters nearby
+}
+
+impl TrapKind
+{
+    pub fn get_name(&self) -> &'static str
+    {
+        match self
+        {
+            TrapKind::PressurePlate => "Pressure Plate",
+            TrapKind::Pit => "Pit",
+            TrapKind::DartWall => "Dart Wall",
+            TrapKind::AlarmRune => "Alarm Rune",
+        }
+    }
+
+    /// Gets how much of the trap budget of a room the trap costs
+    pub fn get_threat(&self) -> u32
+    {
+        match self
+        {
+            TrapKind::PressurePlate => 4,
+            TrapKind::Pit => 6,
+            TrapKind::DartWall => 5,
+            TrapKind::AlarmRune => 3,
+        }
+    }
+
+    /// Gets damage dealt by the trap when triggered
+    pub fn get_damage(&self) -> u32
+    {
+        match self
+        {
+            TrapKind::PressurePlate => 4,
+            TrapKind::Pit => 6,
+            TrapKind::DartWall => 3,
+            TrapKind::AlarmRune => 0,
+        }
+    }
+
+    /// Gets how far from the trap cell it can be triggered, diagonals included
+    pub fn get_trigger_radius(&self) -> usize
+    {
+        match self
+        {
+            TrapKind::AlarmRune => 1,
+            _ => 0
+        }
+    }
+
+    /// Gets what happens when the trap is triggered
+    pub fn get_effect(&self) -> &'static str
+    {
+        match self
+        {
+            TrapKind::PressurePlate => "arrows",
+            TrapKind::Pit => "fall",
+            TrapKind::DartWall => "poison",
+            TrapKind::AlarmRune => "alarm",
+        }
+    }
+}
+
+/// A trap put on the map
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trap
+{
+    /// An unique id of the trap
+    pub id: usize,
+    pub kind: TrapKind,
+    /// X position of the trap
+    pub x: usize,
+    /// Y position of the trap
+    pub y: usize,
+    /// Floor cells not farther from the trap than the radius trigger it, diagonals included
+    pub trigger_radius: usize,
+    /// What happens when the trap is triggered, e.g. "poison"
+    pub effect: String,
+    pub damage: u32,
+    /// Hidden traps should not be shown to the player until revealed
+    pub hidden: bool,
+}
+
+impl Trap
+{
+    /// Creates a hidden trap with the default trigger area and effect of its kind
+    pub fn new(tid: usize, k: TrapKind, xp: usize, yp: usize) -> Self
+    {
+        Self { id: tid, kind: k, x: xp, y: yp, trigger_radius: k.get_trigger_radius(), effect: k.get_effect().to_string(),
+            damage: k.get_damage(), hidden: true }
+    }
+
+    /// Returns true if the cell at given position is in the trigger area of the trap
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn is_triggered_at(&self, x: usize, y: usize) -> bool
+    {
+        self.x.abs_diff(x) <= self.trigger_radius && self.y.abs_diff(y) <= self.trigger_radius
+    }
+}
+
+impl DungeonMap
+{
+    /// Returns true if the cell is a passage one cell wide, like corridor cells and their corners,
+    /// so the player can't walk around it: no two passable neighbours of the cell touch each other
+    /// * 'x' - X coordinate
+    /// * 'y' - Y coordinate
+    pub fn is_chokepoint(&self, x: usize, y: usize) -> bool
+    {
+        let passable = |dx: isize, dy: isize| match (x.checked_add_signed(dx), y.checked_add_signed(dy))
+        {
+            (Some(nx), Some(ny)) => self.is_passable(nx, ny, DoorPassage::All),
+            _ => false
+        };
+
+        if !passable(0, 0)
+        {
+            return false;
+        }
+
+        let neighbours: Vec<(isize, isize)> = [(-1, 0), (1, 0), (0, -1), (0, 1)].into_iter().filter(|&(dx, dy)| passable(dx, dy)).collect();
+
+        //Two neighbours touch only through the diagonal cell between them, opposite ones never do
+        let way_around = neighbours.iter().filter(|&&(_, dy)| dy == 0)
+            .any(|&(dx, _)| neighbours.iter().filter(|&&(dx, _)| dx == 0).any(|&(_, dy)| passable(dx, dy)));
+
+        !neighbours.is_empty() && !way_around
+    }
+
+    /// Gets cells of the trigger area the trap can be put with, leaving out cells with items or monsters.
+    /// None if the area is too close to other traps
+    fn get_trap_area(&self, trap: &Trap) -> Option<Vec<(usize, usize)>>
+    {
+        let (r, near) = (trap.trigger_radius, trap.trigger_radius + 1);
+
+        let crowded = (trap.x.saturating_sub(near)..=trap.x + near)
+            .any(|x| (trap.y.saturating_sub(near)..=trap.y + near).any(|y| matches!(self.get_feature(x, y), Some(Feature::Trap(_)))));
+
+        match crowded
+        {
+            true => None,
+            false => Some((trap.x.saturating_sub(r)..=trap.x + r)
+                .flat_map(|x| (trap.y.saturating_sub(r)..=trap.y + r).map(move |y| (x, y)))
+                .filter(|&(x, y)| self.get_tile(x, y) == Some(DungeonTile::TileEmpty as u8))
+                .filter(|&(x, y)| self.item_at(x, y).is_none() && self.monster_at(x, y).is_none())
+                .collect())
+        }
+    }
+
+    /// Spends trap budgets of rooms on traps put on chokepoints of the room and of corridors leading to it.
+    /// Traps are never put under items, monsters or stairs and never next to other traps
+    pub(crate) fn create_traps(&mut self, dungeon: &Dungeon)
+    {
+        let mut rng = thread_rng();
+        let mut traps = Vec::new();
+
+        for r in 0..dungeon.get_rooms_number()
+        {
+            let room = dungeon.get_room(r).unwrap();
+
+            if room.trap_budget == 0
+            {
+                continue;
+            }
+
+            let corridors: Vec<usize> = dungeon.get_room_neighbours(room.id).iter().map(|&(c, _)| c).collect();
+            let mut free: Vec<(usize, usize)> = Vec::new();
+
+            (0..self.get_height()).for_each(|y| {
+                (0..self.get_width()).for_each(|x| {
+                    let inside = match self.get_region(x, y)
+                    {
+                        Some(Region::Corridor(c)) => corridors.contains(&c),
+                        Some(Region::Room(id)) => id == room.id,
+                        _ => false
+                    };
+
+                    if inside && self.get_tile(x, y) == Some(DungeonTile::TileEmpty as u8) && self.is_chokepoint(x, y)
+                        && self.item_at(x, y).is_none() && self.monster_at(x, y).is_none()
+                    {
+                        free.push((x, y));
+                    }
+                });
+            });
+
+            free.shuffle(&mut rng);
+
+            let mut budget = room.trap_budget;
+
+            while let Some((x, y)) = free.pop()
+            {
+                let affordable: Vec<TrapKind> = TRAP_KINDS.iter().filter(|k| k.get_threat() <= budget).copied().collect();
+
+                let kind = match affordable.choose(&mut rng)
+                {
+                    Some(k) => *k,
+                    None => break
+                };
+
+                let trap = Trap::new(traps.len(), kind, x, y);
+
+                if let Some(area) = self.get_trap_area(&trap)
+                {
+                    area.into_iter().for_each(|(ax, ay)| self.set_feature(ax, ay, Feature::Trap(trap.id)));
+                    budget -= kind.get_threat();
+                    traps.push(trap);
+                }
+            }
+        }
+
+        self.set_traps(traps);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::ascii::GlyphTable;
+    use crate::corridor::Corridor;
+    use crate::difficulty::DifficultySettings;
+    use crate::dungeon::DungeonType;
+    use crate::loot::LootTables;
+    use crate::monster::SpawnTable;
+    use crate::room::Room;
+
+    #[test]
+    fn create_traps_test()
+    {
+        let mut d = Dungeon::new();
+        d.generate(10, DungeonType::Basement, 80, 80, 8, 8).unwrap();
+        d.add_doors().unwrap();
+        d.plan_difficulty(&DifficultySettings::default(), &SpawnTable::default(), &LootTables::default()).unwrap();
+
+        let mut dm = DungeonMap::new(80, 80);
+        dm.create_map(&d);
+
+        let budget: u32 = (0..d.get_rooms_number()).map(|r| d.get_room(r).unwrap().trap_budget).sum();
+        let threat: u32 = dm.get_traps().iter().map(|t| t.kind.get_threat()).sum();
+
+        assert!(!dm.get_traps().is_empty());
+        assert!(threat <= budget);
+
+        for trap in dm.get_traps().to_vec()
+        {
+            assert!(dm.is_chokepoint(trap.x, trap.y));
+            assert!(trap.hidden);
+            assert_eq!(dm.get_tile(trap.x, trap.y), Some(DungeonTile::TileEmpty as u8));
+            assert!(dm.item_at(trap.x, trap.y).is_none() && dm.monster_at(trap.x, trap.y).is_none());
+            assert_eq!(dm.trap_at(trap.x, trap.y), Some(&trap));
+
+            (0..dm.get_height()).for_each(|y| {
+                (0..dm.get_width()).for_each(|x| {
+                    if dm.get_feature(x, y) == Some(Feature::Trap(trap.id))
+                    {
+                        assert!(trap.is_triggered_at(x, y));
+                    }
+                });
+            });
+
+            assert!(dm.reveal_trap(trap.id));
+            assert!(!dm.trap_at(trap.x, trap.y).unwrap().hidden);
+        }
+
+        assert!(!dm.reveal_trap(dm.get_traps().len()));
+    }
+
+    #[test]
+    fn create_traps_fixed_map_test()
+    {
+        //Two rooms joined by a corridor with no doors. Only room 1 has a trap budget, enough for one alarm rune
+        let mut rooms = vec![Room::new(0, 2, 2, 5, 5), Room::new(1, 14, 2, 5, 5)];
+        rooms[1].trap_budget = TrapKind::AlarmRune.get_threat();
+
+        let d = Dungeon::from_parts(rooms, vec![Corridor::new(0, 0, 1, None, None)]);
+        let mut dm = DungeonMap::new(24, 10);
+        dm.create_map(&d);
+
+        let traps = dm.get_traps().to_vec();
+
+        assert_eq!(traps.len(), 1);
+        assert_eq!(traps[0].kind, TrapKind::AlarmRune);
+        assert!(dm.is_chokepoint(traps[0].x, traps[0].y));
+        assert!(matches!(dm.get_region(traps[0].x, traps[0].y), Some(Region::Corridor(0)) | Some(Region::Room(1))));
+
+        //Every cell of the trigger area is marked, the trap and its area stay on the floor
+        for x in traps[0].x - 1..=traps[0].x + 1
+        {
+            for y in traps[0].y - 1..=traps[0].y + 1
+            {
+                let floor = dm.get_tile(x, y) == Some(DungeonTile::TileEmpty as u8);
+
+                assert_eq!(dm.get_feature(x, y) == Some(Feature::Trap(0)), floor);
+            }
+        }
+    }
+
+    #[test]
+    fn is_chokepoint_test()
+    {
+        let dm = DungeonMap::from_ascii("....\n.###\n.#..\n", &GlyphTable::default()).unwrap();
+
+        //Cells on the map edges are checked too
+        assert!(dm.is_chokepoint(1, 0));
+        assert!(dm.is_chokepoint(0, 1));
+        assert!(dm.is_chokepoint(3, 2));
+        assert!(!dm.is_chokepoint(1, 1));
+        assert!(!dm.is_chokepoint(4, 0));
+
+        //A corner of a corridor has no other way around, a corner of an open area has
+        assert!(dm.is_chokepoint(0, 0));
+
+        let open = DungeonMap::from_ascii("...\n...\n.#.\n", &GlyphTable::default()).unwrap();
+
+        assert!(!open.is_chokepoint(0, 0));
+        assert!(!open.is_chokepoint(1, 0));
+        assert!(open.is_chokepoint(0, 2));
+    }
+
+    #[test]
+    fn trap_area_test()
+    {
+        //The alarm rune covers cells next to it, but not the one with the monster
+        let dm = DungeonMap::from_ascii("#####\n..M..\n#####\n", &GlyphTable::default()).unwrap();
+        let area = dm.get_trap_area(&Trap::new(0, TrapKind::AlarmRune, 1, 1)).unwrap();
+
+        assert_eq!(area, [(0, 1), (1, 1)]);
+    }
+}
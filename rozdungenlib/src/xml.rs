@@ -0,0 +1,8 @@
+/// Escapes text put in XML attributes and elements, used by the SVG and Tiled exports
+pub(crate) fn escape_xml(text: &str) -> String
+{
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
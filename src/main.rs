@@ -1,7 +1,7 @@
 use std::{env, rc::Rc};
 use std::path::PathBuf;
 use ggez::graphics::{Canvas, Color};
-use rozdungenlib::{dungeon::Dungeon, dungeonmap::{DungeonMap, DungeonTile}};
+use rozdungenlib::{decoration::PropKind, dungeon::Dungeon, dungeonmap::{DungeonMap, DungeonTile}, light::LightMap, monster::SpawnTable};
 use ggez::{
     event,  
     glam::*,
@@ -21,43 +21,112 @@ const TILE_SIZE: u16 = 32;
 const MAX_ROOM_WIDTH: u16 = 5;
 const MAX_ROOM_HEIGHT: u16 = 4;
 const MAX_ROOMS_TO_GENERATE: u16 = 6;
+const AMBIENT_LIGHT: f32 = 0.25;
+const DARK_ROOMS_CHANCE: u8 = 20;
+const LOCKED_DOORS: usize = 2;
+const SECRET_DOORS: usize = 1;
+const HIDDEN_ROOMS: usize = 1;
+const DUNGEON_LEVEL: u32 = 1;
 
 struct MainState     
 {
     instances: graphics::InstanceArray,
     map: Rc<Vec<Vec<u8>>>,
+    light_map: LightMap,
     open_door_image: graphics::Image,
     closed_door_image: graphics::Image,
     chest: graphics::Image,
     key: graphics::Image,
+    monster: graphics::Mesh,
+    stairs_up: graphics::Mesh,
+    stairs_down: graphics::Mesh,
+    monsters: Vec<(usize, usize)>,
+    prop: graphics::Mesh,
+    props: Vec<(usize, usize, PropKind)>,
 }
 
 impl MainState
 {
-    fn new(ctx: &mut Context, d: &mut Dungeon) -> GameResult<MainState> 
+    fn new(ctx: &mut Context, d: &mut Dungeon, dm: &mut DungeonMap) -> GameResult<MainState> 
     {
         let image = Image::from_path(ctx, "/wall.png")?;
         let mut inst = graphics::InstanceArray::new(ctx, image);
 
-        let map_width = WINDOW_WIDTH as usize / TILE_SIZE as usize;
-        let map_height = WINDOW_HEIGHT as usize / TILE_SIZE as usize;
+        let map_width = dm.get_width();
+        let map_height = dm.get_height();
 
-        let mut dm = DungeonMap::new(map_width, map_height);
-        let map = dm.create_map(d);
+        dm.populate(d);
+        dm.decorate(d);
+        let map: Vec<Vec<u8>> = (0..map_width).map(|x| (0..map_height).map(|y| dm.get_tile(x, y).unwrap()).collect()).collect();
+        let light_map = dm.compute_light_map(d, AMBIENT_LIGHT);
+        let monsters = dm.get_monster_placements().iter().map(|m| (m.x, m.y)).collect();
+        let props = (0..map_height).flat_map(|y| (0..map_width).map(move |x| (x, y)))
+            .filter_map(|(x, y)| dm.prop_at(x, y).map(|p| (x, y, p)))
+            .collect();
+        let half_tile = TILE_SIZE as f32 / 2.0;
 
-        self::MainState::create_instances_from_map(&mut inst, map);
+        self::MainState::create_instances_from_map(&mut inst, &map, &light_map);
 
         Ok(MainState {
             instances: inst,
-            map: Rc::new(map.clone()),
+            map: Rc::new(map),
+            light_map,
             open_door_image: graphics::Image::from_path(ctx, "/door_open.png")?,
             closed_door_image: graphics::Image::from_path(ctx, "/door_closed.png")?,
             chest: graphics::Image::from_path(ctx, "/chest.png")?,
             key: graphics::Image::from_path(ctx, "/key.png")?,
+            monster: graphics::Mesh::new_circle(ctx, graphics::DrawMode::fill(), Vec2::new(half_tile, half_tile),
+                half_tile * 0.6, 0.5, Color::new(0.8, 0.15, 0.15, 1.0))?,
+            monsters,
+            prop: graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(),
+                graphics::Rect::new(0.0, 0.0, TILE_SIZE as f32, TILE_SIZE as f32), Color::WHITE)?,
+            props,
+            stairs_up: Self::create_stairs_mesh(ctx, Color::new(0.4, 0.8, 0.4, 1.0))?,
+            stairs_down: Self::create_stairs_mesh(ctx, Color::new(0.3, 0.5, 0.9, 1.0))?,
         })
     }
 
-    fn create_instances_from_map(inst: &mut graphics::InstanceArray, map: &Vec<Vec<u8>>)
+    /// Creates a tile sized square with steps drawn as lines
+    fn create_stairs_mesh(ctx: &mut Context, color: Color) -> GameResult<graphics::Mesh>
+    {
+        let size = TILE_SIZE as f32;
+        let mut builder = graphics::MeshBuilder::new();
+
+        builder.rectangle(graphics::DrawMode::fill(), graphics::Rect::new(0.0, 0.0, size, size), color)?;
+
+        for i in 1..4
+        {
+            let x = size * i as f32 / 4.0;
+            builder.line(&[Vec2::new(x, 0.0), Vec2::new(x, size)], 2.0, Color::BLACK)?;
+        }
+
+        Ok(graphics::Mesh::from_data(ctx, builder.build()))
+    }
+
+    /// Gets a colour and a size relative to the tile the prop is drawn with
+    fn get_prop_look(prop: PropKind) -> (Color, f32)
+    {
+        match prop
+        {
+            PropKind::Pillar => (Color::new(0.5, 0.5, 0.5, 1.0), 0.7),
+            PropKind::Table => (Color::new(0.63, 0.44, 0.24, 1.0), 0.8),
+            PropKind::Bookshelf => (Color::new(0.35, 0.23, 0.12, 1.0), 1.0),
+            PropKind::Altar => (Color::new(0.88, 0.88, 0.88, 1.0), 0.8),
+            PropKind::Rubble => (Color::new(0.56, 0.56, 0.56, 1.0), 0.3),
+            PropKind::Bones => (Color::new(0.94, 0.94, 0.88, 1.0), 0.3),
+            PropKind::Carpet => (Color::new(0.63, 0.13, 0.25, 1.0), 1.0),
+        }
+    }
+
+    /// Gets a colour the tile is tinted with according to the light falling on it
+    fn get_tint(light_map: &LightMap, x: usize, y: usize) -> Color
+    {
+        let (r, g, b) = light_map.get_light(x, y).unwrap_or((1.0, 1.0, 1.0));
+
+        Color::new(r, g, b, 1.0)
+    }
+
+    fn create_instances_from_map(inst: &mut graphics::InstanceArray, map: &[Vec<u8>], light_map: &LightMap)
     {
         (0..WINDOW_HEIGHT as usize / TILE_SIZE as usize).for_each(|y| {
             let tile_y = y * TILE_SIZE as usize;
@@ -70,7 +139,8 @@ impl MainState
                     inst.push(DrawParam::new()
                     .dest(Vec2::new(tile_x as f32, tile_y as f32))
                     .scale(Vec2::new(1.0, 1.0))
-                    .rotation(0.0));  
+                    .rotation(0.0)
+                    .color(Self::get_tint(light_map, x, y)));  
                 }
             });
         });
@@ -78,7 +148,16 @@ impl MainState
 
     pub fn draw_elements(&self, canvas: &mut Canvas)
     {
-        let color = Color::from((255, 255, 255, 255));
+        self.props.iter().for_each(|&(x, y, prop)| {
+            let (color, size) = Self::get_prop_look(prop);
+            let tint = Self::get_tint(&self.light_map, x, y);
+            let margin = TILE_SIZE as f32 * (1.0 - size) / 2.0;
+
+            canvas.draw(&self.prop, DrawParam::new()
+                .dest(Vec2::new((x * TILE_SIZE as usize) as f32 + margin, (y * TILE_SIZE as usize) as f32 + margin))
+                .scale(Vec2::new(size, size))
+                .color(Color::new(color.r * tint.r, color.g * tint.g, color.b * tint.b, 1.0)));
+        });
 
         (0..WINDOW_HEIGHT as usize / TILE_SIZE as usize).for_each(|y| {
             let tile_y = y * TILE_SIZE as usize;
@@ -86,6 +165,7 @@ impl MainState
             (0..WINDOW_WIDTH as usize / TILE_SIZE as usize).for_each(|x| {
                 let tile_x = x * TILE_SIZE as usize;
                 let tile = self.map[x][y];
+                let color = Self::get_tint(&self.light_map, x, y);
 
                 if tile == DungeonTile::TileOpenDoor as u8
                 {
@@ -111,8 +191,26 @@ impl MainState
                         .dest(Vec2::new(tile_x as f32, tile_y as f32))
                         .color(color));                
                 }
+                else if tile == DungeonTile::TileStairsUp as u8
+                {
+                    canvas.draw(&self.stairs_up, DrawParam::new()
+                        .dest(Vec2::new(tile_x as f32, tile_y as f32))
+                        .color(color));
+                }
+                else if tile == DungeonTile::TileStairsDown as u8
+                {
+                    canvas.draw(&self.stairs_down, DrawParam::new()
+                        .dest(Vec2::new(tile_x as f32, tile_y as f32))
+                        .color(color));
+                }
             });
         });
+
+        self.monsters.iter().for_each(|&(x, y)| {
+            canvas.draw(&self.monster, DrawParam::new()
+                .dest(Vec2::new((x * TILE_SIZE as usize) as f32, (y * TILE_SIZE as usize) as f32))
+                .color(Self::get_tint(&self.light_map, x, y)));
+        });
     }
 }
 
@@ -182,10 +280,21 @@ pub fn main() -> GameResult
     };
 
     dungeon.add_doors().unwrap();
+
+    let mut dm = DungeonMap::new(WINDOW_WIDTH as usize / TILE_SIZE as usize, WINDOW_HEIGHT as usize / TILE_SIZE as usize);
+    dm.create_map(dungeon);
+
+    dungeon.lock_doors(&dm, LOCKED_DOORS, true).unwrap();
+    dungeon.add_stairs(&dm, false).unwrap();
+    dungeon.add_secret_doors(&dm, SECRET_DOORS).unwrap();
+    dungeon.add_hidden_rooms(&dm, HIDDEN_ROOMS).unwrap();
+    dungeon.assign_room_roles().unwrap();
     dungeon.add_items(true);
+    dungeon.add_lights(DARK_ROOMS_CHANCE);
+    dungeon.add_monsters(&SpawnTable::default(), DUNGEON_LEVEL);
 
     let (mut context, event_loop) = context_builder.build()?;
-    let state = MainState::new(&mut context, dungeon).unwrap();
+    let state = MainState::new(&mut context, dungeon, &mut dm).unwrap();
 
     event::run(context, event_loop, state)    
 }
\ No newline at end of file